@@ -0,0 +1,52 @@
+// pixie/benches/resize.rs
+use criterion::{criterion_group, criterion_main, Criterion};
+use image::{imageops, DynamicImage, Rgb32FImage};
+use pixie::{ConvolutionResizer, ResampleFilter, ResizeAlgorithm, Resizer};
+
+fn source() -> Rgb32FImage {
+    Rgb32FImage::from_fn(4000, 3000, |x, y| {
+        let v = ((x * 7 + y * 13) % 256) as f32 / 255.0;
+        image::Rgb([v, 1.0 - v, (x % 17) as f32 / 16.0])
+    })
+}
+
+fn bench_lanczos3(c: &mut Criterion) {
+    let image = source();
+    let mut group = c.benchmark_group("lanczos3 4000x3000 -> 1000x750");
+    group.sample_size(10);
+
+    group.bench_function("imageops", |b| {
+        b.iter(|| imageops::resize(&image, 1000, 750, imageops::FilterType::Lanczos3))
+    });
+    group.bench_function("convolution (scalar)", |b| {
+        let resizer = ConvolutionResizer::new(ResampleFilter::lanczos3()).without_simd();
+        b.iter(|| resizer.resize(&image, 1000, 750))
+    });
+    group.bench_function("convolution", |b| {
+        let resizer = ConvolutionResizer::new(ResampleFilter::lanczos3());
+        b.iter(|| resizer.resize(&image, 1000, 750))
+    });
+
+    group.finish();
+}
+
+/// The common case: an 8-bit RGB photo, including the conversion to and from `f32`.
+fn bench_lanczos3_rgb8(c: &mut Criterion) {
+    let image = DynamicImage::ImageRgb32F(source()).to_rgb8();
+    let dynamic = DynamicImage::ImageRgb8(image.clone());
+    let mut group = c.benchmark_group("lanczos3 8-bit RGB 4000x3000 -> 1000x750");
+    group.sample_size(10);
+
+    group.bench_function("imageops", |b| {
+        b.iter(|| imageops::resize(&image, 1000, 750, imageops::FilterType::Lanczos3))
+    });
+    group.bench_function("resizer", |b| {
+        let resizer = Resizer::new(ResizeAlgorithm::Lanczos3, false).with_linear_light(false);
+        b.iter(|| resizer.resize_exact(&dynamic, 1000, 750))
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_lanczos3, bench_lanczos3_rgb8);
+criterion_main!(benches);
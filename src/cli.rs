@@ -1,5 +1,5 @@
 // pixie/src/cli.rs
-use clap::{Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use std::path::PathBuf;
 
 #[derive(Debug, Clone, ValueEnum)]
@@ -8,6 +8,11 @@ pub enum Algorithm {
     Bilinear,
     Bicubic,
     Lanczos3,
+    Lanczos2,
+    Mitchell,
+    Gaussian,
+    Box,
+    Hermite,
 }
 
 impl From<Algorithm> for crate::ResizeAlgorithm {
@@ -17,6 +22,94 @@ impl From<Algorithm> for crate::ResizeAlgorithm {
             Algorithm::Bilinear => crate::ResizeAlgorithm::Bilinear,
             Algorithm::Bicubic => crate::ResizeAlgorithm::Bicubic,
             Algorithm::Lanczos3 => crate::ResizeAlgorithm::Lanczos3,
+            Algorithm::Lanczos2 => crate::ResizeAlgorithm::Lanczos2,
+            Algorithm::Mitchell => crate::ResizeAlgorithm::Mitchell,
+            Algorithm::Gaussian => crate::ResizeAlgorithm::Gaussian,
+            Algorithm::Box => crate::ResizeAlgorithm::Box,
+            Algorithm::Hermite => crate::ResizeAlgorithm::Hermite,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum ResizePolicy {
+    Always,
+    ShrinkOnly,
+    EnlargeOnly,
+}
+
+impl From<ResizePolicy> for crate::ResizePolicy {
+    fn from(value: ResizePolicy) -> Self {
+        match value {
+            ResizePolicy::Always => crate::ResizePolicy::Always,
+            ResizePolicy::ShrinkOnly => crate::ResizePolicy::ShrinkOnly,
+            ResizePolicy::EnlargeOnly => crate::ResizePolicy::EnlargeOnly,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum Fit {
+    /// Fit inside WIDTHxHEIGHT
+    Inside,
+    /// Fill WIDTHxHEIGHT and crop the overflow
+    Cover,
+    /// Fit inside WIDTHxHEIGHT and pad to exactly that size
+    Pad,
+}
+
+impl From<Fit> for crate::Fit {
+    fn from(value: Fit) -> Self {
+        match value {
+            Fit::Inside => crate::Fit::Inside,
+            Fit::Cover => crate::Fit::Cover,
+            Fit::Pad => crate::Fit::Pad,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum Gravity {
+    Center,
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl From<Gravity> for crate::Gravity {
+    fn from(value: Gravity) -> Self {
+        match value {
+            Gravity::Center => crate::Gravity::Center,
+            Gravity::North => crate::Gravity::North,
+            Gravity::NorthEast => crate::Gravity::NorthEast,
+            Gravity::East => crate::Gravity::East,
+            Gravity::SouthEast => crate::Gravity::SouthEast,
+            Gravity::South => crate::Gravity::South,
+            Gravity::SouthWest => crate::Gravity::SouthWest,
+            Gravity::West => crate::Gravity::West,
+            Gravity::NorthWest => crate::Gravity::NorthWest,
+        }
+    }
+}
+
+#[derive(Debug, Clone, ValueEnum)]
+pub enum WatermarkLayout {
+    Single,
+    Tile,
+    Diagonal,
+}
+
+impl From<WatermarkLayout> for crate::WatermarkLayout {
+    fn from(value: WatermarkLayout) -> Self {
+        match value {
+            WatermarkLayout::Single => crate::WatermarkLayout::Single,
+            WatermarkLayout::Tile => crate::WatermarkLayout::Tile,
+            WatermarkLayout::Diagonal => crate::WatermarkLayout::Diagonal,
         }
     }
 }
@@ -25,6 +118,7 @@ impl From<Algorithm> for crate::ResizeAlgorithm {
 pub enum OutputFormat {
     Jpeg,
     Png,
+    #[value(alias = "webp")]
     WebP,
     Same,
 }
@@ -40,6 +134,411 @@ impl From<OutputFormat> for crate::OutputFormat {
     }
 }
 
+/// Metadata and orientation flags shared by the processing commands.
+#[derive(Args, Debug, Clone)]
+pub struct MetadataArgs {
+    /// Strip metadata (EXIF, XMP, IPTC, ICC, text chunks)
+    #[arg(short = 'm', long)]
+    pub strip_metadata: bool,
+
+    /// EXIF tags or groups (gps, serial, camera, datetime) to keep when stripping
+    #[arg(long, value_delimiter = ',', value_name = "TAGS")]
+    pub keep_metadata: Vec<String>,
+
+    /// EXIF tags or groups (gps, serial, camera, datetime) to remove
+    #[arg(long, value_delimiter = ',', value_name = "TAGS")]
+    pub drop_metadata: Vec<String>,
+
+    /// Do not rotate/flip the image according to its EXIF orientation
+    #[arg(long)]
+    pub no_auto_orient: bool,
+
+    /// Convert colors from the embedded ICC profile to sRGB
+    #[arg(long)]
+    pub to_srgb: bool,
+
+    /// Do not embed the source ICC profile in the output
+    #[arg(long)]
+    pub no_icc: bool,
+}
+
+impl MetadataArgs {
+    pub fn rules(&self) -> crate::MetadataRules {
+        crate::MetadataRules {
+            keep: self.keep_metadata.clone(),
+            drop: self.drop_metadata.clone(),
+        }
+    }
+}
+
+/// Resampling filter options.
+#[derive(Args, Debug, Clone)]
+pub struct ResampleArgs {
+    /// Resize algorithm
+    #[arg(short = 'A', long, value_enum, default_value_t = Algorithm::Lanczos3)]
+    pub algorithm: Algorithm,
+
+    /// Filter in linear light (default for lanczos3 and bicubic)
+    #[arg(long, conflicts_with = "no_linear_light")]
+    pub linear_light: bool,
+
+    /// Filter on the sRGB-encoded values
+    #[arg(long)]
+    pub no_linear_light: bool,
+
+    /// Unsharp mask after resizing: auto, or RADIUS,AMOUNT[,THRESHOLD] (e.g. 0.8,0.6,2)
+    #[arg(long, value_name = "SHARPEN")]
+    pub sharpen: Option<crate::Sharpen>,
+}
+
+impl ResampleArgs {
+    /// The explicit linear-light choice, or `None` for the algorithm's default.
+    pub fn linear_light(&self) -> Option<bool> {
+        match (self.linear_light, self.no_linear_light) {
+            (true, _) => Some(true),
+            (_, true) => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Color and tone adjustments.
+#[derive(Args, Debug, Clone)]
+pub struct AdjustArgs {
+    /// Brightness offset, from -1 to 1
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true, value_name = "AMOUNT")]
+    pub brightness: f32,
+
+    /// Contrast change, from -1 (flat) to 1
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true, value_name = "AMOUNT")]
+    pub contrast: f32,
+
+    /// Saturation change, from -1 (grayscale) to 1
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true, value_name = "AMOUNT")]
+    pub saturation: f32,
+
+    /// Gamma correction (above 1 brightens midtones)
+    #[arg(long, default_value_t = 1.0, value_name = "GAMMA")]
+    pub gamma: f32,
+
+    /// Hue rotation in degrees
+    #[arg(long, default_value_t = 0.0, allow_negative_numbers = true, value_name = "DEGREES")]
+    pub hue: f32,
+}
+
+impl AdjustArgs {
+    pub fn adjustments(&self) -> crate::Adjustments {
+        crate::Adjustments {
+            brightness: self.brightness,
+            contrast: self.contrast,
+            saturation: self.saturation,
+            gamma: self.gamma,
+            hue: self.hue,
+        }
+    }
+}
+
+/// Logo watermark options.
+#[derive(Args, Debug, Clone)]
+pub struct WatermarkArgs {
+    /// Logo to composite onto every output, usually a PNG with transparency
+    #[arg(long, value_name = "LOGO")]
+    pub watermark: Option<PathBuf>,
+
+    /// Where the watermark is placed
+    #[arg(long, value_enum, default_value_t = Gravity::SouthEast, requires = "watermark")]
+    pub watermark_gravity: Gravity,
+
+    /// Distance from the edges (and between tiled copies) in pixels
+    #[arg(long, default_value_t = 16, value_name = "PIXELS", requires = "watermark")]
+    pub watermark_margin: u32,
+
+    /// Watermark width as a fraction of the image width (0 keeps the logo size)
+    #[arg(long, default_value_t = 0.2, value_name = "FRACTION", requires = "watermark")]
+    pub watermark_scale: f32,
+
+    /// Watermark opacity, from 0 to 1
+    #[arg(long, default_value_t = 0.8, value_name = "OPACITY", requires = "watermark")]
+    pub watermark_opacity: f32,
+
+    /// Repeat the watermark over the image
+    #[arg(long, value_enum, default_value_t = WatermarkLayout::Single, requires = "watermark")]
+    pub watermark_layout: WatermarkLayout,
+}
+
+impl WatermarkArgs {
+    pub fn watermark(&self) -> Option<crate::Watermark> {
+        self.watermark.as_ref().map(|path| crate::Watermark {
+            path: path.clone(),
+            gravity: self.watermark_gravity.clone().into(),
+            margin: self.watermark_margin,
+            scale: self.watermark_scale,
+            opacity: self.watermark_opacity,
+            layout: self.watermark_layout.clone().into(),
+        })
+    }
+}
+
+/// Text overlay options.
+#[derive(Args, Debug, Clone)]
+pub struct TextArgs {
+    /// Text to draw onto every output, e.g. a copyright notice ('\n' starts a new line)
+    #[arg(long, value_name = "TEXT")]
+    pub text: Option<String>,
+
+    /// TrueType or OpenType font file [default: bundled DejaVu Sans]
+    #[arg(long, value_name = "FONT", requires = "text")]
+    pub font: Option<PathBuf>,
+
+    /// Font size in pixels
+    #[arg(long, default_value_t = 32.0, value_name = "PIXELS", requires = "text")]
+    pub text_size: f32,
+
+    /// Text color: #rrggbb, #rrggbbaa (alpha sets the opacity), white or black
+    #[arg(long, default_value = "white", value_name = "COLOR", value_parser = parse_fill, requires = "text")]
+    pub text_color: [u8; 4],
+
+    /// Outline width in pixels
+    #[arg(long, default_value_t = 0, value_name = "PIXELS", requires = "text")]
+    pub text_stroke: u32,
+
+    /// Outline color
+    #[arg(long, default_value = "black", value_name = "COLOR", value_parser = parse_fill, requires = "text")]
+    pub text_stroke_color: [u8; 4],
+
+    /// Drop shadow offset in pixels, right and down, e.g. 2,2
+    #[arg(long, value_name = "DX,DY", value_parser = parse_offset, allow_negative_numbers = true, requires = "text")]
+    pub text_shadow: Option<(i32, i32)>,
+
+    /// Drop shadow color
+    #[arg(long, default_value = "#000000a0", value_name = "COLOR", value_parser = parse_fill, requires = "text")]
+    pub text_shadow_color: [u8; 4],
+
+    /// Where the text is placed
+    #[arg(long, value_enum, default_value_t = Gravity::SouthEast, requires = "text")]
+    pub text_gravity: Gravity,
+
+    /// Distance from the edges in pixels
+    #[arg(long, default_value_t = 16, value_name = "PIXELS", requires = "text")]
+    pub text_margin: u32,
+}
+
+impl TextArgs {
+    pub fn text_overlay(&self) -> Option<crate::TextOverlay> {
+        self.text.as_ref().map(|text| crate::TextOverlay {
+            // Let shells pass line breaks as a literal \n
+            text: text.replace("\\n", "\n"),
+            font: self.font.clone(),
+            size: self.text_size,
+            color: self.text_color,
+            stroke_width: self.text_stroke,
+            stroke_color: self.text_stroke_color,
+            shadow: self.text_shadow,
+            shadow_color: self.text_shadow_color,
+            gravity: self.text_gravity.clone().into(),
+            margin: self.text_margin,
+        })
+    }
+}
+
+/// Redaction of faces, license plates and other areas.
+#[derive(Args, Debug, Clone)]
+pub struct RedactArgs {
+    /// Area to hide as x,y,width,height in pixels, or relative with decimals or percentages
+    /// (e.g. 0.1,0.5,0.2,0.1); repeat for more areas
+    #[arg(long, value_name = "REGION")]
+    pub redact: Vec<crate::Region>,
+
+    /// Also hide the areas listed in a JSON sidecar next to each input (photo.jpg.json)
+    #[arg(long)]
+    pub redact_sidecars: bool,
+
+    /// How areas are hidden: blur[:SIGMA], pixelate[:BLOCK] or fill[:COLOR]
+    #[arg(long, default_value = "blur", value_name = "STYLE")]
+    pub redact_style: crate::RedactStyle,
+}
+
+impl RedactArgs {
+    pub fn redaction(&self) -> Option<crate::Redaction> {
+        (!self.redact.is_empty() || self.redact_sidecars).then(|| crate::Redaction {
+            regions: self.redact.clone(),
+            style: self.redact_style,
+            sidecars: self.redact_sidecars,
+        })
+    }
+}
+
+/// Rotations and flips.
+#[derive(Args, Debug, Clone)]
+pub struct TransformArgs {
+    /// Rotate clockwise by DEGREES; multiples of 90 are lossless
+    #[arg(long, allow_negative_numbers = true, value_name = "DEGREES")]
+    pub rotate: Option<f32>,
+
+    /// Mirror left to right (after rotating)
+    #[arg(long)]
+    pub flip_horizontal: bool,
+
+    /// Mirror top to bottom (after rotating)
+    #[arg(long)]
+    pub flip_vertical: bool,
+
+    /// Mirror across the top-left to bottom-right diagonal (before rotating)
+    #[arg(long, conflicts_with = "transverse")]
+    pub transpose: bool,
+
+    /// Mirror across the top-right to bottom-left diagonal (before rotating)
+    #[arg(long)]
+    pub transverse: bool,
+
+    /// Corner fill for arbitrary rotations: a color (#rrggbb, #rrggbbaa, white, black) or transparent
+    #[arg(long, default_value = "white", value_name = "COLOR", value_parser = parse_fill)]
+    pub fill: [u8; 4],
+
+    /// Crop arbitrary rotations to the largest rectangle without fill
+    #[arg(long)]
+    pub crop_rotated: bool,
+}
+
+impl TransformArgs {
+    /// The requested transforms in the order they are applied.
+    pub fn transforms(&self) -> Vec<crate::Transform> {
+        let mut transforms = Vec::new();
+        if self.transpose {
+            transforms.push(crate::Transform::Transpose);
+        }
+        if self.transverse {
+            transforms.push(crate::Transform::Transverse);
+        }
+        if let Some(degrees) = self.rotate {
+            transforms.push(crate::Transform::Rotate {
+                degrees,
+                background: self.fill,
+                crop: self.crop_rotated,
+            });
+        }
+        if self.flip_horizontal {
+            transforms.push(crate::Transform::FlipHorizontal);
+        }
+        if self.flip_vertical {
+            transforms.push(crate::Transform::FlipVertical);
+        }
+        transforms
+    }
+}
+
+fn parse_fill(value: &str) -> Result<[u8; 4], String> {
+    match value.parse::<crate::Background>().map_err(|e| e.to_string())? {
+        crate::Background::Color(rgba) => Ok(rgba),
+        crate::Background::Transparent => Ok([0, 0, 0, 0]),
+        crate::Background::Blur => Err("blur is not a valid fill color".to_string()),
+    }
+}
+
+fn parse_offset(value: &str) -> Result<(i32, i32), String> {
+    let invalid = || format!("invalid offset '{}', expected DX,DY", value);
+    let (dx, dy) = value.split_once(',').ok_or_else(invalid)?;
+    Ok((
+        dx.trim().parse().map_err(|_| invalid())?,
+        dy.trim().parse().map_err(|_| invalid())?,
+    ))
+}
+
+/// Aspect-preserving size limits, as alternatives to WIDTH/HEIGHT.
+#[derive(Args, Debug, Clone)]
+pub struct SizeArgs {
+    /// Scale so the longer side is this many pixels
+    #[arg(long, value_name = "PIXELS", conflicts_with_all = ["shortest_edge", "max_pixels"])]
+    pub longest_edge: Option<u32>,
+
+    /// Scale so the shorter side is this many pixels
+    #[arg(long, value_name = "PIXELS", conflicts_with = "max_pixels")]
+    pub shortest_edge: Option<u32>,
+
+    /// Shrink to at most this many pixels, e.g. 12MP or 2000000
+    #[arg(long, value_name = "PIXELS", value_parser = parse_pixels)]
+    pub max_pixels: Option<u64>,
+}
+
+impl SizeArgs {
+    pub fn is_set(&self) -> bool {
+        self.longest_edge.is_some() || self.shortest_edge.is_some() || self.max_pixels.is_some()
+    }
+}
+
+fn parse_pixels(value: &str) -> Result<u64, String> {
+    let lower = value.trim().to_lowercase();
+    let (number, multiplier) = match lower.strip_suffix("mp") {
+        Some(number) => (number, 1_000_000.0),
+        None => (lower.as_str(), 1.0),
+    };
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid pixel count: {}", value))?;
+    if !number.is_finite() || number <= 0.0 {
+        return Err(format!("Invalid pixel count: {}", value));
+    }
+    Ok((number * multiplier).round() as u64)
+}
+
+/// How the image is fitted into WIDTHxHEIGHT.
+#[derive(Args, Debug, Clone)]
+pub struct FitArgs {
+    /// Fit mode when both width and height are given
+    #[arg(long, value_enum, default_value_t = Fit::Inside)]
+    pub fit: Fit,
+
+    /// Part of the image to keep when cropping, or where to place it when padding
+    #[arg(long, value_enum, default_value_t = Gravity::Center)]
+    pub gravity: Gravity,
+
+    /// Padding fill: a color (#rrggbb, #rrggbbaa, white, black), transparent or blur
+    #[arg(long, default_value = "white", value_name = "BACKGROUND")]
+    pub background: crate::Background,
+
+    /// Focal point to keep when cropping, as relative X,Y (e.g. 0.3,0.25); overrides --gravity
+    #[arg(long, value_name = "X,Y", value_parser = parse_focus)]
+    pub focus: Option<(f32, f32)>,
+}
+
+impl FitArgs {
+    pub fn gravity(&self) -> crate::Gravity {
+        match self.focus {
+            Some((x, y)) => crate::Gravity::Focus(x, y),
+            None => self.gravity.clone().into(),
+        }
+    }
+}
+
+fn parse_focus(value: &str) -> Result<(f32, f32), String> {
+    let (x, y) = value
+        .split_once(',')
+        .ok_or_else(|| format!("Expected X,Y but got '{}'", value))?;
+    let parse = |v: &str| v.trim().parse::<f32>().map_err(|e| format!("Invalid coordinate '{}': {}", v, e));
+    Ok((parse(x)?, parse(y)?))
+}
+
+/// Output size and quality targets shared by the encoding commands.
+#[derive(Args, Debug, Clone)]
+pub struct TargetArgs {
+    /// Largest output size, e.g. 200KB or 1.5MB (lowers quality until it fits)
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    pub target_size: Option<u64>,
+
+    /// Shrink the image when quality alone cannot reach --target-size
+    #[arg(long, requires = "target_size")]
+    pub allow_downscale: bool,
+
+    /// Use the lowest JPEG quality whose output reaches this SSIM, e.g. 0.98
+    #[arg(long, value_name = "SSIM")]
+    pub target_ssim: Option<f64>,
+}
+
+fn parse_size(value: &str) -> Result<u64, String> {
+    crate::parse_file_size(value).map_err(|e| e.to_string())
+}
+
 #[derive(Parser)]
 #[command(name = "image-tool")]
 #[command(version, about = "Fast image resizer and optimizer", long_about = None)]
@@ -60,11 +559,11 @@ pub struct Cli {
 pub enum Commands {
     /// Resize a single image
     Resize {
-        /// Input image file
+        /// Input image file ('-' for stdin)
         #[arg(value_name = "INPUT")]
         input: PathBuf,
 
-        /// Output image file (default: input_resized.ext)
+        /// Output image file, '-' for stdout (default: input_resized.ext)
         #[arg(short, long, value_name = "OUTPUT")]
         output: Option<PathBuf>,
 
@@ -84,7 +583,7 @@ pub enum Commands {
         #[arg(short, long, default_value_t = 85, value_name = "QUALITY")]
         quality: u8,
 
-        /// Output format
+        /// Output format (required when writing to stdout)
         #[arg(short = 'f', long, value_enum, value_name = "FORMAT")]
         format: Option<OutputFormat>,
 
@@ -92,13 +591,36 @@ pub enum Commands {
         #[arg(short = 'a', long)]
         keep_aspect: bool,
 
-        /// Strip metadata (EXIF, etc.)
-        #[arg(short = 'm', long)]
-        strip_metadata: bool,
+        #[command(flatten)]
+        metadata: MetadataArgs,
+
+        #[command(flatten)]
+        redact: RedactArgs,
+
+        #[command(flatten)]
+        target: TargetArgs,
+
+        #[command(flatten)]
+        resample: ResampleArgs,
 
-        /// Resize algorithm
-        #[arg(short = 'A', long, value_enum, default_value_t = Algorithm::Lanczos3)]
-        algorithm: Algorithm,
+        #[command(flatten)]
+        adjust: AdjustArgs,
+
+        #[command(flatten)]
+        watermark: WatermarkArgs,
+
+        #[command(flatten)]
+        text: TextArgs,
+
+        #[command(flatten)]
+        size: SizeArgs,
+
+        #[command(flatten)]
+        fit: FitArgs,
+
+        /// Whether images may be enlarged and/or shrunk
+        #[arg(long, value_enum, default_value_t = ResizePolicy::Always)]
+        resize_policy: ResizePolicy,
 
         /// Use progressive JPEG encoding
         #[arg(long)]
@@ -115,9 +637,9 @@ pub enum Commands {
         #[arg(short, long, value_name = "OUTPUT_DIR")]
         output: PathBuf,
 
-        /// Width in pixels
-        #[arg(short = 'W', long, default_value_t = 800, value_name = "PIXELS")]
-        width: u32,
+        /// Width in pixels [default: 800 unless another size option is given]
+        #[arg(short = 'W', long, value_name = "PIXELS")]
+        width: Option<u32>,
 
         /// Height in pixels (0 for auto)
         #[arg(short = 'H', long, default_value_t = 0, value_name = "PIXELS")]
@@ -139,13 +661,39 @@ pub enum Commands {
         #[arg(short, long)]
         recursive: bool,
 
-        /// Strip metadata
-        #[arg(short = 'm', long)]
-        strip_metadata: bool,
+        #[command(flatten)]
+        metadata: MetadataArgs,
+
+        #[command(flatten)]
+        redact: RedactArgs,
 
-        /// Resize algorithm
-        #[arg(short = 'A', long, value_enum, default_value_t = Algorithm::Lanczos3)]
-        algorithm: Algorithm,
+        #[command(flatten)]
+        target: TargetArgs,
+
+        #[command(flatten)]
+        transform: TransformArgs,
+
+        #[command(flatten)]
+        resample: ResampleArgs,
+
+        #[command(flatten)]
+        adjust: AdjustArgs,
+
+        #[command(flatten)]
+        watermark: WatermarkArgs,
+
+        #[command(flatten)]
+        text: TextArgs,
+
+        #[command(flatten)]
+        size: SizeArgs,
+
+        #[command(flatten)]
+        fit: FitArgs,
+
+        /// Whether images may be enlarged and/or shrunk
+        #[arg(long, value_enum, default_value_t = ResizePolicy::Always)]
+        resize_policy: ResizePolicy,
 
         /// Disable PNG optimization
         #[arg(long)]
@@ -154,11 +702,11 @@ pub enum Commands {
 
     /// Optimize image without resizing
     Optimize {
-        /// Input image file
+        /// Input image file ('-' for stdin)
         #[arg(value_name = "INPUT")]
         input: PathBuf,
 
-        /// Output image file
+        /// Output image file, '-' for stdout (default: input_optimized.ext)
         #[arg(short, long, value_name = "OUTPUT")]
         output: Option<PathBuf>,
 
@@ -166,9 +714,18 @@ pub enum Commands {
         #[arg(short, long, default_value_t = 85, value_name = "QUALITY")]
         quality: u8,
 
-        /// Strip metadata
-        #[arg(short = 'm', long)]
-        strip_metadata: bool,
+        /// Output format (required when writing to stdout)
+        #[arg(short = 'f', long, value_enum, value_name = "FORMAT")]
+        format: Option<OutputFormat>,
+
+        #[command(flatten)]
+        metadata: MetadataArgs,
+
+        #[command(flatten)]
+        redact: RedactArgs,
+
+        #[command(flatten)]
+        target: TargetArgs,
 
         /// Use progressive JPEG encoding
         #[arg(long)]
@@ -191,11 +748,11 @@ pub enum Commands {
 
     /// Convert image to another format
     Convert {
-        /// Input image file
+        /// Input image file ('-' for stdin)
         #[arg(value_name = "INPUT")]
         input: PathBuf,
 
-        /// Output image file (default: input_converted.ext)
+        /// Output image file, '-' for stdout (default: input_converted.ext)
         #[arg(short, long, value_name = "OUTPUT")]
         output: Option<PathBuf>,
 
@@ -207,8 +764,155 @@ pub enum Commands {
         #[arg(short, long, default_value_t = 85, value_name = "QUALITY")]
         quality: u8,
 
-        /// Strip metadata
-        #[arg(short = 'm', long)]
-        strip_metadata: bool,
+        #[command(flatten)]
+        metadata: MetadataArgs,
+
+        #[command(flatten)]
+        target: TargetArgs,
+    },
+
+    /// Rotate or mirror an image
+    Transform {
+        /// Input image file ('-' for stdin)
+        #[arg(value_name = "INPUT")]
+        input: PathBuf,
+
+        /// Output image file, '-' for stdout (default: input_transformed.ext)
+        #[arg(short, long, value_name = "OUTPUT")]
+        output: Option<PathBuf>,
+
+        /// Output format (required when writing to stdout)
+        #[arg(short = 'f', long, value_enum, value_name = "FORMAT")]
+        format: Option<OutputFormat>,
+
+        /// JPEG quality (1-100)
+        #[arg(short, long, default_value_t = 85, value_name = "QUALITY")]
+        quality: u8,
+
+        #[command(flatten)]
+        metadata: MetadataArgs,
+
+        #[command(flatten)]
+        transform: TransformArgs,
+    },
+
+    /// Write several widths and formats of an image for responsive web pages
+    Responsive {
+        /// Input image file
+        #[arg(value_name = "INPUT")]
+        input: PathBuf,
+
+        /// Output directory
+        #[arg(short, long, value_name = "OUTPUT_DIR")]
+        output: PathBuf,
+
+        /// Comma-separated widths in pixels; widths above the source width become the source width
+        #[arg(short = 'W', long, value_delimiter = ',', default_value = "320,640,1024,1920", value_name = "PIXELS")]
+        widths: Vec<u32>,
+
+        /// Comma-separated output formats, each written at every width
+        #[arg(short = 'f', long, value_enum, value_delimiter = ',', default_value = "same", value_name = "FORMATS")]
+        formats: Vec<OutputFormat>,
+
+        /// Output file names: {name} (input file stem), {width}, {height} and {ext}
+        #[arg(long, default_value = "{name}-{width}w.{ext}", value_name = "TEMPLATE")]
+        name_template: String,
+
+        /// JPEG quality (1-100)
+        #[arg(short, long, default_value_t = 85, value_name = "QUALITY")]
+        quality: u8,
+
+        #[command(flatten)]
+        metadata: MetadataArgs,
+
+        #[command(flatten)]
+        resample: ResampleArgs,
+
+        /// Write the <picture> snippet to FILE ('-' prints it)
+        #[arg(long, default_value = "-", value_name = "FILE")]
+        html: PathBuf,
+
+        /// Write the JSON manifest to FILE ('-' prints it) [default: OUTPUT_DIR/{name}.json]
+        #[arg(long, value_name = "FILE")]
+        manifest: Option<PathBuf>,
+
+        /// The sizes attribute of the snippet
+        #[arg(long, default_value = "100vw", value_name = "SIZES")]
+        sizes: String,
+
+        /// Alternative text of the snippet's <img>
+        #[arg(long, default_value = "", value_name = "TEXT")]
+        alt: String,
+
+        /// Prefix of the file names in the snippet, e.g. /images/
+        #[arg(long, default_value = "", value_name = "URL")]
+        url_prefix: String,
+    },
+
+    /// Lay out thumbnails of a directory on paginated proof sheets
+    ContactSheet {
+        /// Input directory
+        #[arg(value_name = "INPUT_DIR")]
+        input: PathBuf,
+
+        /// Output file; several pages are written as NAME-1.EXT, NAME-2.EXT, ...
+        #[arg(short, long, default_value = "contact-sheet.jpg", value_name = "OUTPUT")]
+        output: PathBuf,
+
+        /// Recursively process subdirectories
+        #[arg(short, long)]
+        recursive: bool,
+
+        /// Thumbnails per row
+        #[arg(long, default_value_t = 5, value_name = "COUNT")]
+        columns: u32,
+
+        /// Rows per page (0 puts every image on one page)
+        #[arg(long, default_value_t = 6, value_name = "COUNT")]
+        rows: u32,
+
+        /// Longest side of each thumbnail in pixels
+        #[arg(long, default_value_t = 240, value_name = "PIXELS")]
+        thumb_size: u32,
+
+        /// Gap between thumbnails in pixels
+        #[arg(long, default_value_t = 16, value_name = "PIXELS")]
+        spacing: u32,
+
+        /// Page color: a color (#rrggbb, #rrggbbaa, white, black) or transparent
+        #[arg(long, default_value = "white", value_name = "COLOR", value_parser = parse_fill)]
+        background: [u8; 4],
+
+        /// Leave out the file name captions
+        #[arg(long)]
+        no_filenames: bool,
+
+        /// Caption thumbnails with the EXIF capture date and exposure
+        #[arg(long)]
+        exif: bool,
+
+        /// Caption font size in pixels
+        #[arg(long, default_value_t = 13.0, value_name = "PIXELS")]
+        caption_size: f32,
+
+        /// Caption color
+        #[arg(long, default_value = "black", value_name = "COLOR", value_parser = parse_fill)]
+        caption_color: [u8; 4],
+
+        /// TrueType or OpenType font file for captions [default: bundled DejaVu Sans]
+        #[arg(long, value_name = "FILE")]
+        font: Option<PathBuf>,
+
+        /// JPEG quality (1-100)
+        #[arg(short, long, default_value_t = 85, value_name = "QUALITY")]
+        quality: u8,
+
+        /// Output format [default: from the output extension]
+        #[arg(short = 'f', long, value_enum, value_name = "FORMAT")]
+        format: Option<OutputFormat>,
+
+        /// Number of parallel threads (0 = auto)
+        #[arg(short, long, default_value_t = 0, value_name = "THREADS")]
+        threads: usize,
     },
 }
\ No newline at end of file
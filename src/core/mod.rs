@@ -1,17 +1,451 @@
 // pixie/src/core/mod.rs
-use std::path::Path;
+use std::path::PathBuf;
+use std::str::FromStr;
 use thiserror::Error;
 
+pub mod pipeline;
 pub mod processor;
 
+pub use pipeline::*;
 pub use processor::*;
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ResizeAlgorithm {
     Nearest,
     Bilinear,
+    /// Catmull-Rom cubic.
     Bicubic,
     Lanczos3,
+    Lanczos2,
+    /// Mitchell-Netravali cubic (B = C = 1/3): less ringing than Bicubic.
+    Mitchell,
+    Gaussian,
+    /// Area average: every source pixel in the footprint counts equally.
+    Box,
+    Hermite,
+}
+
+/// Which direction of scaling a resize is allowed to go.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ResizePolicy {
+    #[default]
+    Always,
+    /// Never enlarge; smaller images keep their original size.
+    ShrinkOnly,
+    /// Never shrink; larger images keep their original size.
+    EnlargeOnly,
+}
+
+/// How an image is fitted into a width x height box.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Fit {
+    /// Scale down (or up) to fit inside the box.
+    #[default]
+    Inside,
+    /// Scale to fill the whole box and crop the overflow according to the gravity.
+    Cover,
+    /// Fit inside the box, then place the image on a canvas of exactly that size.
+    Pad,
+}
+
+/// Canvas fill used by [`Fit::Pad`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Background {
+    /// Solid RGBA color.
+    Color([u8; 4]),
+    Transparent,
+    /// A blurred copy of the image stretched over the canvas.
+    Blur,
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Background::Color([255, 255, 255, 255])
+    }
+}
+
+impl FromStr for Background {
+    type Err = ImageToolError;
+
+    /// Accepts `transparent`, `blur`, `white`, `black`, `#rgb`, `#rrggbb` or `#rrggbbaa`.
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || ImageToolError::InvalidParameter(format!("Invalid background: {}", value));
+
+        match value.trim().to_lowercase().as_str() {
+            "transparent" => Ok(Background::Transparent),
+            "blur" => Ok(Background::Blur),
+            "white" => Ok(Background::Color([255, 255, 255, 255])),
+            "black" => Ok(Background::Color([0, 0, 0, 255])),
+            hex => {
+                let hex = hex.strip_prefix('#').unwrap_or(hex);
+                // Checked first so the byte slicing below stays on char boundaries
+                if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                    return Err(invalid());
+                }
+                let hex: String = match hex.len() {
+                    3 => hex.chars().flat_map(|c| [c, c]).collect(),
+                    6 | 8 => hex.to_string(),
+                    _ => return Err(invalid()),
+                };
+                let mut rgba = [255u8; 4];
+                for (i, channel) in rgba.iter_mut().enumerate().take(hex.len() / 2) {
+                    *channel = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+                }
+                Ok(Background::Color(rgba))
+            }
+        }
+    }
+}
+
+/// The part of the image kept when cropping, or where it is placed when padding.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Gravity {
+    #[default]
+    Center,
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+    /// Focal point in relative coordinates, from (0, 0) top-left to (1, 1) bottom-right.
+    Focus(f32, f32),
+}
+
+impl Gravity {
+    /// Relative position of the gravity, from (0, 0) top-left to (1, 1) bottom-right.
+    pub fn anchor(&self) -> (f32, f32) {
+        match *self {
+            Gravity::Center => (0.5, 0.5),
+            Gravity::North => (0.5, 0.0),
+            Gravity::NorthEast => (1.0, 0.0),
+            Gravity::East => (1.0, 0.5),
+            Gravity::SouthEast => (1.0, 1.0),
+            Gravity::South => (0.5, 1.0),
+            Gravity::SouthWest => (0.0, 1.0),
+            Gravity::West => (0.0, 0.5),
+            Gravity::NorthWest => (0.0, 0.0),
+            Gravity::Focus(x, y) => (x, y),
+        }
+    }
+}
+
+/// A geometric transformation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Transform {
+    Rotate90,
+    Rotate180,
+    Rotate270,
+    FlipHorizontal,
+    FlipVertical,
+    /// Mirror across the top-left to bottom-right diagonal.
+    Transpose,
+    /// Mirror across the top-right to bottom-left diagonal.
+    Transverse,
+    /// Rotate clockwise by any angle in degrees. The canvas grows to fit the rotated
+    /// image and the corners are filled with `background` (RGBA), unless `crop` trims
+    /// the result to the largest rectangle that holds only image pixels.
+    Rotate { degrees: f32, background: [u8; 4], crop: bool },
+}
+
+/// How a watermark is repeated over the image.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WatermarkLayout {
+    /// One copy, placed by the gravity.
+    #[default]
+    Single,
+    /// A grid of copies covering the whole image.
+    Tile,
+    /// Copies tilted 30° counter-clockwise, in staggered rows covering the whole image.
+    Diagonal,
+}
+
+/// A logo composited onto the output.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    /// Path of the logo, usually a PNG with transparency.
+    pub path: PathBuf,
+    /// Where a single watermark is placed.
+    pub gravity: Gravity,
+    /// Distance from the image edges, and between tiled copies, in pixels.
+    pub margin: u32,
+    /// Logo width as a fraction of the image width; 0 keeps the logo's own size.
+    pub scale: f32,
+    /// From 0 (invisible) to 1 (as drawn).
+    pub opacity: f32,
+    pub layout: WatermarkLayout,
+}
+
+impl Watermark {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            gravity: Gravity::SouthEast,
+            margin: 16,
+            scale: 0.2,
+            opacity: 0.8,
+            layout: WatermarkLayout::Single,
+        }
+    }
+}
+
+/// Text drawn onto the output, such as a copyright notice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextOverlay {
+    /// The text; `\n` starts a new line.
+    pub text: String,
+    /// TrueType or OpenType font file; `None` uses the bundled DejaVu Sans.
+    pub font: Option<PathBuf>,
+    /// Font size in pixels.
+    pub size: f32,
+    /// Text color (RGBA); the alpha sets the opacity.
+    pub color: [u8; 4],
+    /// Outline width in pixels; 0 draws no outline.
+    pub stroke_width: u32,
+    pub stroke_color: [u8; 4],
+    /// Drop shadow offset in pixels, right and down; `None` draws no shadow.
+    pub shadow: Option<(i32, i32)>,
+    pub shadow_color: [u8; 4],
+    /// Where the text is placed; lines are aligned to the same side.
+    pub gravity: Gravity,
+    /// Distance from the image edges in pixels.
+    pub margin: u32,
+}
+
+impl TextOverlay {
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            font: None,
+            size: 32.0,
+            color: [255, 255, 255, 255],
+            stroke_width: 0,
+            stroke_color: [0, 0, 0, 255],
+            shadow: None,
+            shadow_color: [0, 0, 0, 160],
+            gravity: Gravity::SouthEast,
+            margin: 16,
+        }
+    }
+}
+
+/// A rectangle of the image, such as an area to redact.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Region {
+    Pixels { x: u32, y: u32, width: u32, height: u32 },
+    /// In fractions of the image width and height, from 0 to 1.
+    Relative { x: f32, y: f32, width: f32, height: f32 },
+}
+
+impl Region {
+    /// `x, y, width, height` in pixels of a `width` x `height` image, clipped to it;
+    /// `None` when nothing of the region is inside.
+    pub fn to_pixels(&self, image_width: u32, image_height: u32) -> Option<(u32, u32, u32, u32)> {
+        let (x0, y0, x1, y1) = match *self {
+            Region::Pixels { x, y, width, height } => (
+                x as u64,
+                y as u64,
+                x as u64 + width as u64,
+                y as u64 + height as u64,
+            ),
+            Region::Relative { x, y, width, height } => {
+                let scale = |value: f32, size: u32| (value.max(0.0) * size as f32).round() as u64;
+                (
+                    scale(x, image_width),
+                    scale(y, image_height),
+                    scale(x + width, image_width),
+                    scale(y + height, image_height),
+                )
+            }
+        };
+
+        let (x1, y1) = (x1.min(image_width as u64), y1.min(image_height as u64));
+        if x0 >= x1 || y0 >= y1 {
+            return None;
+        }
+        Some((x0 as u32, y0 as u32, (x1 - x0) as u32, (y1 - y0) as u32))
+    }
+}
+
+impl FromStr for Region {
+    type Err = ImageToolError;
+
+    /// Accepts `x,y,width,height` in pixels, or relative to the image size when any value
+    /// has a decimal point or percent sign (`0.1,0.5,0.2,0.1` or `10%,50%,20%,10%`).
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || {
+            ImageToolError::InvalidParameter(format!(
+                "Invalid region: {} (expected x,y,width,height)",
+                value
+            ))
+        };
+
+        let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+        if parts.len() != 4 {
+            return Err(invalid());
+        }
+
+        if parts.iter().any(|part| part.contains('.') || part.ends_with('%')) {
+            let mut values = [0f32; 4];
+            for (slot, part) in values.iter_mut().zip(&parts) {
+                *slot = match part.strip_suffix('%') {
+                    Some(percent) => percent.parse::<f32>().map_err(|_| invalid())? / 100.0,
+                    None => part.parse().map_err(|_| invalid())?,
+                };
+            }
+            let [x, y, width, height] = values;
+            Ok(Region::Relative { x, y, width, height })
+        } else {
+            let mut values = [0u32; 4];
+            for (slot, part) in values.iter_mut().zip(&parts) {
+                *slot = part.parse().map_err(|_| invalid())?;
+            }
+            let [x, y, width, height] = values;
+            Ok(Region::Pixels { x, y, width, height })
+        }
+    }
+}
+
+/// How redacted regions are hidden.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RedactStyle {
+    /// Gaussian blur; `None` picks a sigma of a sixth of the region's shorter side.
+    Blur { sigma: Option<f32> },
+    /// Blocks of averaged color; `None` picks a sixth of the region's shorter side.
+    Pixelate { block: Option<u32> },
+    /// Solid RGBA color.
+    Fill([u8; 4]),
+}
+
+impl Default for RedactStyle {
+    fn default() -> Self {
+        RedactStyle::Blur { sigma: None }
+    }
+}
+
+impl FromStr for RedactStyle {
+    type Err = ImageToolError;
+
+    /// Accepts `blur[:SIGMA]`, `pixelate[:BLOCK]` or `fill[:COLOR]` (black by default).
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || ImageToolError::InvalidParameter(format!("Invalid redaction style: {}", value));
+        let (name, argument) = match value.trim().split_once(':') {
+            Some((name, argument)) => (name, Some(argument.trim())),
+            None => (value.trim(), None),
+        };
+
+        match name.to_lowercase().as_str() {
+            "blur" => Ok(RedactStyle::Blur {
+                sigma: argument.map(|a| a.parse().map_err(|_| invalid())).transpose()?,
+            }),
+            "pixelate" => Ok(RedactStyle::Pixelate {
+                block: argument.map(|a| a.parse().map_err(|_| invalid())).transpose()?,
+            }),
+            "fill" => match argument.map(Background::from_str).transpose()? {
+                None => Ok(RedactStyle::Fill([0, 0, 0, 255])),
+                Some(Background::Color(rgba)) => Ok(RedactStyle::Fill(rgba)),
+                Some(Background::Transparent) => Ok(RedactStyle::Fill([0, 0, 0, 0])),
+                Some(Background::Blur) => Err(invalid()),
+            },
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Areas hidden before anything else changes the image, e.g. faces and license plates.
+///
+/// Regions refer to the source image after auto-orientation.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Redaction {
+    /// Regions hidden in every image.
+    pub regions: Vec<Region>,
+    pub style: RedactStyle,
+    /// Also hide the regions listed in a `<file name>.json` sidecar next to each source
+    /// file, e.g. `IMG_0042.jpg.json`.
+    pub sidecars: bool,
+}
+
+/// Color and tone adjustments; the default changes nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Adjustments {
+    /// Offset added to every channel, from -1 (black) to 1 (white).
+    pub brightness: f32,
+    /// Stretch around mid-grey, from -1 (flat grey) through 0 to 1 (doubled).
+    pub contrast: f32,
+    /// Chroma change, from -1 (grayscale) through 0 to 1 (doubled).
+    pub saturation: f32,
+    /// Gamma correction; values above 1 brighten the midtones.
+    pub gamma: f32,
+    /// Hue rotation in degrees.
+    pub hue: f32,
+}
+
+impl Adjustments {
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl Default for Adjustments {
+    fn default() -> Self {
+        Self {
+            brightness: 0.0,
+            contrast: 0.0,
+            saturation: 0.0,
+            gamma: 1.0,
+            hue: 0.0,
+        }
+    }
+}
+
+/// Unsharp mask parameters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UnsharpMask {
+    /// Standard deviation of the Gaussian blur, in pixels.
+    pub radius: f32,
+    /// How much of the difference from the blurred image is added back (1.0 = 100%).
+    pub amount: f32,
+    /// Differences smaller than this (0-255) are left alone, so flat areas and noise stay smooth.
+    pub threshold: u8,
+}
+
+/// Sharpening applied after resizing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Sharpen {
+    /// Strength grows with how much the image was shrunk; enlarged images are left alone.
+    Auto,
+    Mask(UnsharpMask),
+}
+
+impl FromStr for Sharpen {
+    type Err = ImageToolError;
+
+    /// Accepts `auto` or `RADIUS,AMOUNT[,THRESHOLD]`, e.g. `0.8,0.6,2`.
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || {
+            ImageToolError::InvalidParameter(format!(
+                "Invalid sharpening '{}': expected auto or RADIUS,AMOUNT[,THRESHOLD]",
+                value
+            ))
+        };
+
+        let value = value.trim();
+        if value.eq_ignore_ascii_case("auto") {
+            return Ok(Sharpen::Auto);
+        }
+
+        let parts: Vec<&str> = value.split(',').map(str::trim).collect();
+        if !(2..=3).contains(&parts.len()) {
+            return Err(invalid());
+        }
+        Ok(Sharpen::Mask(UnsharpMask {
+            radius: parts[0].parse().map_err(|_| invalid())?,
+            amount: parts[1].parse().map_err(|_| invalid())?,
+            threshold: parts.get(2).map_or(Ok(0), |t| t.parse()).map_err(|_| invalid())?,
+        }))
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -19,12 +453,54 @@ pub struct ProcessConfig {
     pub width: u32,
     pub height: u32,
     pub scale: f32,
+    /// Scale so the longer side is this many pixels (0 = unset).
+    pub longest_edge: u32,
+    /// Scale so the shorter side is this many pixels (0 = unset).
+    pub shortest_edge: u32,
+    /// Shrink so the image has at most this many pixels (0 = unset).
+    pub max_pixels: u64,
     pub quality: u8,
     pub keep_aspect: bool,
+    pub resize_policy: ResizePolicy,
+    /// How `width` x `height` is applied when both are set.
+    pub fit: Fit,
+    /// The part of the image kept by [`Fit::Cover`], or its placement with [`Fit::Pad`].
+    pub gravity: Gravity,
+    /// Canvas fill for [`Fit::Pad`].
+    pub background: Background,
     pub strip_metadata: bool,
+    pub metadata_rules: MetadataRules,
+    /// Rotate/flip according to the EXIF Orientation tag before any other step.
+    pub auto_orient: bool,
+    /// Convert pixels from the embedded ICC profile to sRGB (the profile is then dropped).
+    pub convert_to_srgb: bool,
+    /// Embed the source ICC profile in the output when the pixels are left in its color space.
+    pub embed_icc_profile: bool,
     pub algorithm: ResizeAlgorithm,
+    /// Filter in linear light; `None` enables it for Lanczos3 and Bicubic only.
+    pub linear_light: Option<bool>,
+    /// Areas blurred, pixelated or filled right after auto-orientation.
+    pub redaction: Option<Redaction>,
+    /// Rotations and flips, applied in order before resizing.
+    pub transforms: Vec<Transform>,
+    /// Color and tone adjustments applied after resizing.
+    pub adjustments: Adjustments,
+    /// Unsharp mask applied after resizing.
+    pub sharpen: Option<Sharpen>,
+    /// Logo composited onto the image after resizing and sharpening.
+    pub watermark: Option<Watermark>,
+    /// Text drawn onto the image after the watermark.
+    pub text_overlay: Option<TextOverlay>,
     pub max_file_size: Option<u64>,
+    /// Largest allowed output size in bytes; quality is lowered until the output fits.
+    pub target_size: Option<u64>,
+    /// Let `target_size` shrink the image when the lowest quality is still too large.
+    pub downscale_to_target: bool,
+    /// Encode at the lowest quality whose output reaches this SSIM (0-1] against the source.
+    pub target_ssim: Option<f64>,
     pub format: Option<OutputFormat>,
+    pub optimize_png: bool,
+    pub progressive_jpeg: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -35,6 +511,157 @@ pub enum OutputFormat {
     SameAsInput,
 }
 
+/// Several widths and formats of one image, e.g. for an HTML `srcset`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponsiveSpec {
+    /// Output widths in pixels; widths above the source width are replaced by the
+    /// source width itself.
+    pub widths: Vec<u32>,
+    /// Every width is written in each of these formats.
+    pub formats: Vec<OutputFormat>,
+    /// Output file name inside the output directory. `{name}` is the input file stem,
+    /// `{width}` and `{height}` the variant size and `{ext}` the format extension.
+    pub template: String,
+}
+
+impl Default for ResponsiveSpec {
+    fn default() -> Self {
+        Self {
+            widths: vec![320, 640, 1024, 1920],
+            formats: vec![OutputFormat::SameAsInput],
+            template: "{name}-{width}w.{ext}".to_string(),
+        }
+    }
+}
+
+impl ResponsiveSpec {
+    pub fn validate(&self) -> Result<()> {
+        if self.widths.is_empty() || self.widths.contains(&0) {
+            return Err(ImageToolError::InvalidParameter(
+                "Responsive widths must be non-empty and greater than zero".to_string(),
+            ));
+        }
+        if self.formats.is_empty() {
+            return Err(ImageToolError::InvalidParameter(
+                "At least one responsive output format is required".to_string(),
+            ));
+        }
+        if !self.template.contains("{width}") || !self.template.contains("{ext}") {
+            return Err(ImageToolError::InvalidParameter(
+                "Name template must contain {width} and {ext}".to_string(),
+            ));
+        }
+        Self::check_file_name(&self.template)
+    }
+
+    /// The template expanded for one variant.
+    pub fn file_name(&self, name: &str, width: u32, height: u32, extension: &str) -> Result<String> {
+        let file = self
+            .template
+            .replace("{name}", name)
+            .replace("{width}", &width.to_string())
+            .replace("{height}", &height.to_string())
+            .replace("{ext}", extension);
+        Self::check_file_name(&file)?;
+        Ok(file)
+    }
+
+    /// Names must be plain file names so variants stay inside the output directory.
+    fn check_file_name(file: &str) -> Result<()> {
+        if file.contains(['/', '\\']) || file.split('.').all(str::is_empty) {
+            return Err(ImageToolError::SecurityError(format!(
+                "Variant name {:?} must be a file name inside the output directory",
+                file
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Layout of a contact sheet: a grid of captioned thumbnails split into pages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactSheet {
+    pub columns: u32,
+    /// Rows on each page; 0 puts every image on one page.
+    pub rows: u32,
+    /// Longest side of each thumbnail in pixels.
+    pub thumbnail_size: u32,
+    /// Gap between thumbnails and around the grid, in pixels.
+    pub spacing: u32,
+    /// Page color (RGBA).
+    pub background: [u8; 4],
+    /// Caption each thumbnail with its file name.
+    pub filename_captions: bool,
+    /// Caption each thumbnail with the EXIF capture date and exposure.
+    pub exif_captions: bool,
+    /// Caption font size in pixels.
+    pub caption_size: f32,
+    pub caption_color: [u8; 4],
+    /// TrueType or OpenType font file for captions; `None` uses the bundled DejaVu Sans.
+    pub font: Option<PathBuf>,
+}
+
+impl Default for ContactSheet {
+    fn default() -> Self {
+        Self {
+            columns: 5,
+            rows: 6,
+            thumbnail_size: 240,
+            spacing: 16,
+            background: [255, 255, 255, 255],
+            filename_captions: true,
+            exif_captions: false,
+            caption_size: 13.0,
+            caption_color: [0, 0, 0, 255],
+            font: None,
+        }
+    }
+}
+
+impl ContactSheet {
+    pub fn validate(&self) -> Result<()> {
+        if self.columns == 0 || self.columns > 100 {
+            return Err(ImageToolError::InvalidParameter(
+                "Contact sheet columns must be between 1 and 100".to_string(),
+            ));
+        }
+        if self.thumbnail_size == 0 || self.thumbnail_size > 4096 {
+            return Err(ImageToolError::InvalidParameter(
+                "Thumbnail size must be between 1 and 4096 pixels".to_string(),
+            ));
+        }
+        if self.spacing > 1024 {
+            return Err(ImageToolError::InvalidParameter(
+                "Contact sheet spacing must be at most 1024 pixels".to_string(),
+            ));
+        }
+        if !(self.caption_size > 0.0 && self.caption_size <= 256.0) {
+            return Err(ImageToolError::InvalidParameter(
+                "Caption size must be greater than 0 and at most 256 pixels".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Selective metadata handling.
+///
+/// Entries are EXIF tag names as printed by `info --exif` (`Copyright`, `GPSLatitude`, ...)
+/// or one of the groups `gps`, `serial`, `camera` and `datetime`; matching is
+/// case-insensitive. A field survives if it matches `keep`, or if metadata is not being
+/// stripped and it does not match `drop`. The EXIF thumbnail is always removed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MetadataRules {
+    pub keep: Vec<String>,
+    pub drop: Vec<String>,
+}
+
+impl MetadataRules {
+    pub fn is_empty(&self) -> bool {
+        self.keep.is_empty() && self.drop.is_empty()
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct ImageMetadata {
     pub width: u32,
@@ -50,6 +677,12 @@ pub struct ProcessingStats {
     pub total_size_before: u64,
     pub total_size_after: u64,
     pub errors: Vec<(String, String)>,
+    /// Encoder quality picked per file by a size or SSIM target.
+    pub chosen_qualities: Vec<(String, u8)>,
+    /// Measured SSIM per file when encoding against an SSIM target.
+    pub quality_scores: Vec<(String, f64)>,
+    /// Files left at their original size by the resize policy, with the reason.
+    pub skipped_resizes: Vec<(String, String)>,
 }
 
 impl Default for ProcessConfig {
@@ -58,12 +691,35 @@ impl Default for ProcessConfig {
             width: 0,
             height: 0,
             scale: 0.0,
+            longest_edge: 0,
+            shortest_edge: 0,
+            max_pixels: 0,
             quality: 85,
             keep_aspect: true,
+            resize_policy: ResizePolicy::Always,
+            fit: Fit::Inside,
+            gravity: Gravity::Center,
+            background: Background::default(),
             strip_metadata: false,
+            metadata_rules: MetadataRules::default(),
+            auto_orient: true,
+            convert_to_srgb: false,
+            embed_icc_profile: true,
             algorithm: ResizeAlgorithm::Lanczos3,
+            linear_light: None,
+            redaction: None,
+            transforms: Vec::new(),
+            adjustments: Adjustments::default(),
+            sharpen: None,
+            watermark: None,
+            text_overlay: None,
             max_file_size: None,
+            target_size: None,
+            downscale_to_target: false,
+            target_ssim: None,
             format: None,
+            optimize_png: true,
+            progressive_jpeg: false,
         }
     }
 }
@@ -76,12 +732,167 @@ impl ProcessConfig {
             ));
         }
 
-        if self.width > 100_000 || self.height > 100_000 {
+        let size_modes = [
+            self.scale > 0.0 || self.width > 0 || self.height > 0,
+            self.longest_edge > 0,
+            self.shortest_edge > 0,
+            self.max_pixels > 0,
+        ];
+        if size_modes.iter().filter(|set| **set).count() > 1 {
+            return Err(ImageToolError::InvalidParameter(
+                "Specify only one of width/height/scale, longest edge, shortest edge or max pixels"
+                    .to_string(),
+            ));
+        }
+
+        if self.width > 100_000
+            || self.height > 100_000
+            || self.longest_edge > 100_000
+            || self.shortest_edge > 100_000
+        {
             return Err(ImageToolError::InvalidParameter(
                 "Dimensions too large (max 100,000 pixels)".to_string(),
             ));
         }
 
+        if self.fit != Fit::Inside && (self.width == 0 || self.height == 0) {
+            return Err(ImageToolError::InvalidParameter(
+                format!("{:?} fit needs both width and height", self.fit),
+            ));
+        }
+
+        if let Gravity::Focus(x, y) = self.gravity {
+            if !(0.0..=1.0).contains(&x) || !(0.0..=1.0).contains(&y) {
+                return Err(ImageToolError::InvalidParameter(
+                    "Focal point coordinates must be between 0 and 1".to_string(),
+                ));
+            }
+        }
+
+        let rules = &self.metadata_rules;
+        if rules.keep.iter().chain(&rules.drop).any(|name| name.trim().is_empty()) {
+            return Err(ImageToolError::InvalidParameter(
+                "Metadata keep/drop rules must not contain empty names".to_string(),
+            ));
+        }
+
+        for transform in &self.transforms {
+            if let Transform::Rotate { degrees, .. } = transform {
+                if !degrees.is_finite() {
+                    return Err(ImageToolError::InvalidParameter(
+                        "Rotation angle must be finite".to_string(),
+                    ));
+                }
+            }
+        }
+
+        let adjustments = &self.adjustments;
+        let in_unit_range = |value: f32| (-1.0..=1.0).contains(&value);
+        if !in_unit_range(adjustments.brightness)
+            || !in_unit_range(adjustments.contrast)
+            || !in_unit_range(adjustments.saturation)
+        {
+            return Err(ImageToolError::InvalidParameter(
+                "Brightness, contrast and saturation must be between -1 and 1".to_string(),
+            ));
+        }
+        if !(adjustments.gamma > 0.0 && adjustments.gamma <= 10.0) {
+            return Err(ImageToolError::InvalidParameter(
+                "Gamma must be greater than 0 and at most 10".to_string(),
+            ));
+        }
+        if !adjustments.hue.is_finite() {
+            return Err(ImageToolError::InvalidParameter("Hue rotation must be finite".to_string()));
+        }
+
+        if let Some(Sharpen::Mask(mask)) = self.sharpen {
+            if !(mask.radius > 0.0 && mask.radius <= 50.0) {
+                return Err(ImageToolError::InvalidParameter(
+                    "Sharpen radius must be greater than 0 and at most 50".to_string(),
+                ));
+            }
+            if !(mask.amount >= 0.0 && mask.amount <= 10.0) {
+                return Err(ImageToolError::InvalidParameter(
+                    "Sharpen amount must be between 0 and 10".to_string(),
+                ));
+            }
+        }
+
+        if let Some(watermark) = &self.watermark {
+            if !(0.0..=1.0).contains(&watermark.opacity) {
+                return Err(ImageToolError::InvalidParameter(
+                    "Watermark opacity must be between 0 and 1".to_string(),
+                ));
+            }
+            if !(0.0..=1.0).contains(&watermark.scale) {
+                return Err(ImageToolError::InvalidParameter(
+                    "Watermark scale must be between 0 and 1".to_string(),
+                ));
+            }
+        }
+
+        if let Some(redaction) = &self.redaction {
+            if redaction.regions.is_empty() && !redaction.sidecars {
+                return Err(ImageToolError::InvalidParameter(
+                    "Redaction needs at least one region or sidecar files".to_string(),
+                ));
+            }
+            for region in &redaction.regions {
+                if let Region::Relative { x, y, width, height } = *region {
+                    if ![x, y, width, height].iter().all(|v| (0.0..=1.0).contains(v)) {
+                        return Err(ImageToolError::InvalidParameter(
+                            "Relative region values must be between 0 and 1".to_string(),
+                        ));
+                    }
+                }
+            }
+            match redaction.style {
+                RedactStyle::Blur { sigma: Some(sigma) } if !(sigma > 0.0 && sigma <= 1000.0) => {
+                    return Err(ImageToolError::InvalidParameter(
+                        "Redaction blur sigma must be greater than 0 and at most 1000".to_string(),
+                    ));
+                }
+                RedactStyle::Pixelate { block: Some(0) } => {
+                    return Err(ImageToolError::InvalidParameter(
+                        "Redaction block size must be greater than zero".to_string(),
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(overlay) = &self.text_overlay {
+            if overlay.text.trim().is_empty() {
+                return Err(ImageToolError::InvalidParameter(
+                    "Overlay text must not be empty".to_string(),
+                ));
+            }
+            if !(overlay.size > 0.0 && overlay.size <= 2048.0) {
+                return Err(ImageToolError::InvalidParameter(
+                    "Text size must be greater than 0 and at most 2048 pixels".to_string(),
+                ));
+            }
+            if overlay.stroke_width > 255 {
+                return Err(ImageToolError::InvalidParameter(
+                    "Text stroke must be at most 255 pixels".to_string(),
+                ));
+            }
+        }
+
+        if self.target_size == Some(0) {
+            return Err(ImageToolError::InvalidParameter(
+                "Target size must be greater than zero".to_string(),
+            ));
+        }
+
+        if let Some(target) = self.target_ssim {
+            if !(target > 0.0 && target <= 1.0) {
+                return Err(ImageToolError::InvalidParameter(
+                    "Target SSIM must be greater than 0 and at most 1".to_string(),
+                ));
+            }
+        }
+
         if self.quality == 0 || self.quality > 100 {
             return Err(ImageToolError::InvalidParameter(
                 "Quality must be between 1 and 100".to_string(),
@@ -0,0 +1,195 @@
+// pixie/src/core/pipeline.rs
+use super::{ImageToolError, ProcessConfig, Result};
+use crate::processors::{
+    AdjustOperation, AutoOrientOperation, CompressOperation, ConvertToSrgbOperation, MetadataOperation,
+    RedactOperation, ResizeOperation, SharpenOperation, TextOverlayOperation, TransformOperation,
+    WatermarkOperation,
+};
+use image::{DynamicImage, ImageFormat};
+use std::path::PathBuf;
+
+/// State shared by the operations of a single pipeline run.
+#[derive(Debug, Clone)]
+pub struct PipelineContext {
+    pub source_path: Option<PathBuf>,
+    pub source_format: Option<ImageFormat>,
+    pub output_format: ImageFormat,
+    /// Raw EXIF block of the source; operations that change the pixels keep it in sync.
+    pub exif: Option<Vec<u8>>,
+    /// Raw XMP packet of the source, written back next to `exif`.
+    pub xmp: Option<Vec<u8>>,
+    /// ICC profile describing the current pixels; `None` means sRGB.
+    pub icc_profile: Option<Vec<u8>>,
+    /// Scrub every metadata block the encoder wrote before embedding `exif` and `xmp`.
+    pub strip_metadata: bool,
+    /// Why the resize step left the image at its original size, if it did.
+    pub resize_skipped: Option<String>,
+    /// Per-side scale factor (output / input) of the resize step, when it changed the size.
+    pub resize_scale: Option<f64>,
+    /// Encoder quality chosen by a size or SSIM target, if one was searched.
+    pub chosen_quality: Option<u8>,
+    /// SSIM of the output against the encoder input, when a quality target measured it.
+    pub quality_score: Option<f64>,
+    /// Encoded output, set by the encoding operation (usually the last one).
+    pub encoded: Option<Vec<u8>>,
+}
+
+impl PipelineContext {
+    pub fn new(output_format: ImageFormat) -> Self {
+        Self {
+            source_path: None,
+            source_format: None,
+            output_format,
+            exif: None,
+            xmp: None,
+            icc_profile: None,
+            strip_metadata: false,
+            resize_skipped: None,
+            resize_scale: None,
+            chosen_quality: None,
+            quality_score: None,
+            encoded: None,
+        }
+    }
+
+    pub fn with_source_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.source_path = Some(path.into());
+        self
+    }
+
+    pub fn with_source_format(mut self, format: ImageFormat) -> Self {
+        self.source_format = Some(format);
+        self
+    }
+
+    pub fn with_exif(mut self, exif: Option<Vec<u8>>) -> Self {
+        self.exif = exif;
+        self
+    }
+
+    pub fn with_xmp(mut self, xmp: Option<Vec<u8>>) -> Self {
+        self.xmp = xmp;
+        self
+    }
+
+    pub fn with_icc_profile(mut self, icc_profile: Option<Vec<u8>>) -> Self {
+        self.icc_profile = icc_profile;
+        self
+    }
+}
+
+/// A single step of a [`Pipeline`].
+///
+/// Operations take ownership of the image and return the transformed one, so
+/// steps that leave the pixels alone can hand the image back without copying.
+pub trait Operation: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn apply(&self, image: DynamicImage, context: &mut PipelineContext) -> Result<DynamicImage>;
+}
+
+/// An ordered list of operations run against every image.
+#[derive(Default)]
+pub struct Pipeline {
+    operations: Vec<Box<dyn Operation>>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds the built-in pipeline described by `config`:
+    /// auto-orient → sRGB conversion → redaction → metadata rules → transform → resize → adjust →
+    /// sharpen → watermark → text → compress.
+    pub fn from_config(config: &ProcessConfig) -> Self {
+        let mut pipeline = Self::new();
+
+        if config.auto_orient {
+            pipeline.push(AutoOrientOperation::new());
+        }
+
+        if config.convert_to_srgb {
+            pipeline.push(ConvertToSrgbOperation::new());
+        }
+
+        if let Some(redact) = RedactOperation::from_config(config) {
+            pipeline.push(redact);
+        }
+
+        if let Some(metadata) = MetadataOperation::from_config(config) {
+            pipeline.push(metadata);
+        }
+
+        if let Some(transform) = TransformOperation::from_config(config) {
+            pipeline.push(transform);
+        }
+
+        if let Some(resize) = ResizeOperation::from_config(config) {
+            pipeline.push(resize);
+        }
+
+        if let Some(adjust) = AdjustOperation::from_config(config) {
+            pipeline.push(adjust);
+        }
+
+        if let Some(sharpen) = SharpenOperation::from_config(config) {
+            pipeline.push(sharpen);
+        }
+
+        if let Some(watermark) = WatermarkOperation::from_config(config) {
+            pipeline.push(watermark);
+        }
+
+        if let Some(text) = TextOverlayOperation::from_config(config) {
+            pipeline.push(text);
+        }
+
+        pipeline.push(CompressOperation::from_config(config));
+        pipeline
+    }
+
+    pub fn with_operation<O: Operation + 'static>(mut self, operation: O) -> Self {
+        self.push(operation);
+        self
+    }
+
+    pub fn push<O: Operation + 'static>(&mut self, operation: O) {
+        self.operations.push(Box::new(operation));
+    }
+
+    /// Inserts `operation` right before the first operation called `name`.
+    pub fn insert_before<O: Operation + 'static>(&mut self, name: &str, operation: O) -> Result<()> {
+        let index = self
+            .operations
+            .iter()
+            .position(|op| op.name() == name)
+            .ok_or_else(|| {
+                ImageToolError::InvalidParameter(format!("No operation named '{}' in pipeline", name))
+            })?;
+
+        self.operations.insert(index, Box::new(operation));
+        Ok(())
+    }
+
+    pub fn operation_names(&self) -> Vec<&str> {
+        self.operations.iter().map(|op| op.name()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub fn run(&self, mut image: DynamicImage, context: &mut PipelineContext) -> Result<DynamicImage> {
+        for operation in &self.operations {
+            log::debug!("Running operation: {}", operation.name());
+            image = operation.apply(image, context)?;
+        }
+
+        Ok(image)
+    }
+}
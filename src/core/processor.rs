@@ -1,31 +1,47 @@
 // pixie/src/core/processor.rs
-use super::{ImageToolError, ProcessConfig, Result, ImageMetadata, ProcessingStats};
-use crate::processors::{Loader, Resizer, Compressor, MetadataProcessor};
-use std::path::{Path, PathBuf};
+use super::{
+    ImageToolError, ProcessConfig, Result, ImageMetadata, ProcessingStats, OutputFormat,
+    Pipeline, PipelineContext,
+};
+use crate::processors::{Loader, Compressor, MetadataProcessor};
+use image::{DynamicImage, ImageFormat};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::Arc;
 
 pub struct ImageProcessor {
     config: ProcessConfig,
     loader: Loader,
-    resizer: Resizer,
+    pipeline: Arc<Pipeline>,
     compressor: Compressor,
     metadata_processor: MetadataProcessor,
 }
 
 impl ImageProcessor {
     pub fn new(config: ProcessConfig) -> Self {
-        let resizer = Resizer::new(config.algorithm, config.keep_aspect);
+        let pipeline = Pipeline::from_config(&config);
+        Self::with_pipeline(config, pipeline)
+    }
+
+    /// Creates a processor that runs `pipeline` instead of the one derived from `config`.
+    ///
+    /// `config` still controls input limits and the output format.
+    pub fn with_pipeline(config: ProcessConfig, pipeline: impl Into<Arc<Pipeline>>) -> Self {
         let compressor = Compressor::new(config.quality);
-        let metadata_processor = MetadataProcessor::new();
 
         Self {
             config,
             loader: Loader::new(),
-            resizer,
+            pipeline: pipeline.into(),
             compressor,
-            metadata_processor,
+            metadata_processor: MetadataProcessor::new(),
         }
     }
 
+    pub fn pipeline(&self) -> &Pipeline {
+        &self.pipeline
+    }
+
     pub fn process<P: AsRef<Path>>(&self, input_path: P, output_path: P) -> Result<ProcessingStats> {
         self.process_single(input_path, output_path)
     }
@@ -42,56 +58,66 @@ impl ImageProcessor {
 
         // Load image with memory limit check
         let original_size = std::fs::metadata(input_path)?.len();
-        if let Some(max_size) = self.config.max_file_size {
-            if original_size > max_size {
-                return Err(ImageToolError::MemoryLimitExceeded(
-                    format!("File size {} exceeds limit {}", original_size, max_size)
-                ));
-            }
-        }
+        self.check_input_size(original_size)?;
 
-        let mut image = self.loader.load(input_path)?;
-        
-        // Strip metadata if requested
-        if self.config.strip_metadata {
-            self.metadata_processor.strip_metadata(&mut image, input_path)?;
-        }
+        let loaded = self.loader.load_with_metadata(input_path)?;
+        let source_format = self.loader.detect_format(input_path).ok().or(loaded.format);
+        let output_format = self.resolve_output_format(source_format)?;
 
-        // Resize if needed
-        if self.config.width > 0 || self.config.height > 0 || self.config.scale > 0.0 {
-            let mode = if self.config.scale > 0.0 {
-                crate::processors::ResizeMode::Scale(self.config.scale)
-            } else {
-                crate::processors::ResizeMode::Absolute(self.config.width, self.config.height)
-            };
-            
-            image = self.resizer.resize(&image, mode);
-        }
+        let mut context = PipelineContext::new(output_format)
+            .with_source_path(input_path)
+            .with_exif(loaded.exif)
+            .with_xmp(loaded.xmp)
+            .with_icc_profile(loaded.icc_profile);
+        context.source_format = source_format;
 
-        // Determine output format
-        let output_format = match self.config.format {
-            Some(crate::core::OutputFormat::Jpeg) => image::ImageFormat::Jpeg,
-            Some(crate::core::OutputFormat::Png) => image::ImageFormat::Png,
-            Some(crate::core::OutputFormat::WebP) => image::ImageFormat::WebP,
-            _ => self.loader.detect_format(input_path)?,
-        };
+        let encoded = self.run_pipeline(loaded.image, &mut context)?;
+
+        std::fs::write(output_path, &encoded)?;
+        log::info!("Saved image: {} ({} bytes)", output_path.display(), encoded.len());
+
+        Ok(Self::single_stats(input_path.display().to_string(), original_size, &encoded, &context))
+    }
 
-        // Compress and save
-        self.compressor.save_with_format(&image, output_path, output_format)?;
+    /// Processes an encoded image held in memory and returns the encoded result.
+    ///
+    /// The input format is detected from the data itself; nothing touches the filesystem.
+    pub fn process_bytes(&self, data: &[u8]) -> Result<(Vec<u8>, ProcessingStats)> {
+        let original_size = data.len() as u64;
+        self.check_input_size(original_size)?;
+
+        let source_format = self.loader.detect_format_from_bytes(data)?;
+        let loaded = self.loader.load_from_bytes_with_metadata(data)?;
+        let output_format = self.resolve_output_format(Some(source_format))?;
+
+        let mut context = PipelineContext::new(output_format)
+            .with_source_format(source_format)
+            .with_exif(loaded.exif)
+            .with_xmp(loaded.xmp)
+            .with_icc_profile(loaded.icc_profile);
+        let encoded = self.run_pipeline(loaded.image, &mut context)?;
+
+        let stats = Self::single_stats("-".to_string(), original_size, &encoded, &context);
+        Ok((encoded, stats))
+    }
 
-        let new_size = std::fs::metadata(output_path)?.len();
-        
-        let mut stats = ProcessingStats::default();
-        stats.processed_count = 1;
-        stats.total_size_before = original_size;
-        stats.total_size_after = new_size;
-        
+    /// Reads an encoded image from `reader` and returns the encoded result.
+    pub fn process_reader<R: Read>(&self, reader: R) -> Result<(Vec<u8>, ProcessingStats)> {
+        let data = self.read_input(reader)?;
+        self.process_bytes(&data)
+    }
+
+    /// Reads an encoded image from `reader` and writes the encoded result to `writer`.
+    pub fn process_to_writer<R: Read, W: Write>(&self, reader: R, mut writer: W) -> Result<ProcessingStats> {
+        let (encoded, stats) = self.process_reader(reader)?;
+        writer.write_all(&encoded)?;
+        writer.flush()?;
         Ok(stats)
     }
 
     pub fn get_metadata<P: AsRef<Path>>(&self, path: P) -> Result<ImageMetadata> {
         let path = path.as_ref();
-        
+
         if !path.exists() {
             return Err(ImageToolError::InvalidParameter(
                 format!("File does not exist: {}", path.display())
@@ -111,6 +137,70 @@ impl ImageProcessor {
         })
     }
 
+    fn run_pipeline(&self, image: DynamicImage, context: &mut PipelineContext) -> Result<Vec<u8>> {
+        let image = self.pipeline.run(image, context)?;
+
+        // Fall back to the default encoder for pipelines without an encoding step
+        match context.encoded.take() {
+            Some(bytes) => Ok(bytes),
+            None => self.compressor.compress_to_bytes(&image, context.output_format),
+        }
+    }
+
+    fn single_stats(
+        name: String,
+        original_size: u64,
+        encoded: &[u8],
+        context: &PipelineContext,
+    ) -> ProcessingStats {
+        ProcessingStats {
+            processed_count: 1,
+            total_size_before: original_size,
+            total_size_after: encoded.len() as u64,
+            chosen_qualities: context.chosen_quality.map(|q| (name.clone(), q)).into_iter().collect(),
+            quality_scores: context.quality_score.map(|s| (name.clone(), s)).into_iter().collect(),
+            skipped_resizes: context.resize_skipped.clone().map(|r| (name, r)).into_iter().collect(),
+            ..Default::default()
+        }
+    }
+
+    fn check_input_size(&self, size: u64) -> Result<()> {
+        if let Some(max_size) = self.config.max_file_size {
+            if size > max_size {
+                return Err(ImageToolError::MemoryLimitExceeded(
+                    format!("File size {} exceeds limit {}", size, max_size)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_input<R: Read>(&self, mut reader: R) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+
+        // Stop reading one byte past the limit so oversized streams are never fully buffered
+        match self.config.max_file_size {
+            Some(max_size) => (&mut reader).take(max_size.saturating_add(1)).read_to_end(&mut data)?,
+            None => reader.read_to_end(&mut data)?,
+        };
+
+        Ok(data)
+    }
+
+    fn resolve_output_format(&self, source_format: Option<ImageFormat>) -> Result<ImageFormat> {
+        match self.config.format {
+            Some(OutputFormat::Jpeg) => Ok(ImageFormat::Jpeg),
+            Some(OutputFormat::Png) => Ok(ImageFormat::Png),
+            Some(OutputFormat::WebP) => Ok(ImageFormat::WebP),
+            _ => source_format.ok_or_else(|| {
+                ImageToolError::UnsupportedFormat(
+                    "Cannot determine output format from input; specify one explicitly".to_string()
+                )
+            }),
+        }
+    }
+
     fn validate_paths(&self, input_path: &Path, output_path: &Path) -> Result<()> {
         // Security: Prevent path traversal
         if input_path.to_string_lossy().contains("..") {
@@ -137,4 +227,4 @@ impl ImageProcessor {
 
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -3,23 +3,34 @@ mod core;
 mod processors;
 mod utils;
 
-pub use cli::{Algorithm, Cli, Commands};
+pub use cli::{AdjustArgs, Algorithm, RedactArgs, TextArgs, TransformArgs, WatermarkArgs, ResizePolicy as CliResizePolicy, Cli, Commands, FitArgs, MetadataArgs, ResampleArgs, SizeArgs, TargetArgs, OutputFormat as CliOutputFormat};
 pub use core::{
-    ImageProcessor, ImageToolError, ProcessConfig, ResizeAlgorithm, Result, 
-    ImageMetadata, ProcessingStats, validate_config, OutputFormat
+    ImageProcessor, ImageToolError, ProcessConfig, ResizeAlgorithm, ResizePolicy, Background, Fit, Gravity, Result, 
+    ImageMetadata, ProcessingStats, validate_config, OutputFormat,
+    Operation, Pipeline, PipelineContext, MetadataRules, Sharpen, UnsharpMask, Adjustments, Transform,
+    Watermark, WatermarkLayout, TextOverlay, Region, RedactStyle, Redaction,
+    ResponsiveSpec, ContactSheet
 };
 pub use processors::{
-    BatchProcessor, Compressor, Loader, MetadataProcessor, Resizer
+    BatchProcessor, Compressor, Loader, LoadedImage, MetadataProcessor, Resizer, ResizeMode, ssim,
+    ConvolutionResizer, ResampleFilter,
+    AutoOrientOperation, ColorConverter, CompressOperation, ConvertToSrgbOperation, MetadataOperation, ResizeOperation,
+    Sharpener, SharpenOperation, Adjuster, AdjustOperation, Transformer, TransformOperation,
+    Watermarker, WatermarkOperation, TextRenderer, TextOverlayOperation,
+    Redactor, RedactOperation, ResponsiveGenerator, ResponsiveSet, Variant,
+    ContactSheetMaker, ContactSheetPages
 };
 pub use utils::{
-    calculate_aspect_ratio, format_file_size, generate_output_path,
-    get_image_info, is_supported_format, validate_dimensions
+    calculate_aspect_ratio, format_file_size, parse_file_size, generate_output_path,
+    get_image_info, is_supported_format, validate_dimensions,
+    sanitize_filename, get_file_extension, is_stdio_path
 };
 
 pub mod prelude {
     pub use crate::{
         ImageProcessor, ProcessConfig, ResizeAlgorithm,
-        BatchProcessor, Compressor, Loader, MetadataProcessor, Resizer
+        BatchProcessor, Compressor, Loader, MetadataProcessor, Resizer,
+        Operation, Pipeline, PipelineContext
     };
 }
 
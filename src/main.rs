@@ -1,8 +1,13 @@
 // pixie/src/main.rs
-use image_tool::prelude::*;
-use image_tool::{Cli, Commands, Algorithm, OutputFormat};
+use pixie::prelude::*;
+use pixie::{Cli, Commands, CliOutputFormat as OutputFormat, CliResizePolicy, ProcessingStats};
+use pixie::{ContactSheet, ContactSheetMaker, ResponsiveGenerator, ResponsiveSpec};
+use pixie::{AdjustArgs, FitArgs, MetadataArgs, RedactArgs, ResampleArgs, SizeArgs, TargetArgs, TextArgs, TransformArgs, WatermarkArgs};
+use pixie::{format_file_size, generate_output_path, is_stdio_path};
 use clap::Parser;
 use log::LevelFilter;
+use std::io::Write;
+use std::path::Path;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     let cli = Cli::parse();
@@ -28,13 +33,21 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             quality,
             format,
             keep_aspect,
-            strip_metadata,
-            algorithm,
+            metadata,
+            redact,
+            target,
+            resample,
+            adjust,
+            watermark,
+            text,
+            size,
+            fit,
+            resize_policy,
             progressive,
         } => {
             process_resize(
                 input, output, width, height, scale, quality,
-                format, keep_aspect, strip_metadata, algorithm,
+                format, keep_aspect, metadata, redact, target, resample, adjust, watermark, text, size, fit, resize_policy,
                 progressive, max_file_size,
             )?;
         }
@@ -47,13 +60,22 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             quality,
             threads,
             recursive,
-            strip_metadata,
-            algorithm,
+            metadata,
+            redact,
+            target,
+            transform,
+            resample,
+            adjust,
+            watermark,
+            text,
+            size,
+            fit,
+            resize_policy,
             no_png_optimize,
         } => {
             process_batch(
                 input, output, width, height, format, quality,
-                threads, recursive, strip_metadata, algorithm,
+                threads, recursive, metadata, redact, target, transform, resample, adjust, watermark, text, size, fit, resize_policy,
                 no_png_optimize, max_file_size,
             )?;
         }
@@ -61,12 +83,15 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             input,
             output,
             quality,
-            strip_metadata,
+            format,
+            metadata,
+            redact,
+            target,
             progressive,
             no_png_optimize,
         } => {
             process_optimize(
-                input, output, quality, strip_metadata,
+                input, output, quality, format, metadata, redact, target,
                 progressive, no_png_optimize, max_file_size,
             )?;
         }
@@ -78,18 +103,87 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             output,
             format,
             quality,
-            strip_metadata,
+            metadata,
+            target,
         } => {
             process_convert(
                 input, output, format, quality,
-                strip_metadata, max_file_size,
+                metadata, target, max_file_size,
             )?;
         }
+        Commands::Transform {
+            input,
+            output,
+            format,
+            quality,
+            metadata,
+            transform,
+        } => {
+            process_transform(input, output, format, quality, metadata, transform, max_file_size)?;
+        }
+        Commands::Responsive {
+            input,
+            output,
+            widths,
+            formats,
+            name_template,
+            quality,
+            metadata,
+            resample,
+            html,
+            manifest,
+            sizes,
+            alt,
+            url_prefix,
+        } => {
+            let spec = ResponsiveSpec {
+                widths,
+                formats: formats.into_iter().map(Into::into).collect(),
+                template: name_template,
+            };
+            process_responsive(
+                input, output, spec, quality, metadata, resample,
+                html, manifest, &sizes, &alt, &url_prefix, max_file_size,
+            )?;
+        }
+        Commands::ContactSheet {
+            input,
+            output,
+            recursive,
+            columns,
+            rows,
+            thumb_size,
+            spacing,
+            background,
+            no_filenames,
+            exif,
+            caption_size,
+            caption_color,
+            font,
+            quality,
+            format,
+            threads,
+        } => {
+            let sheet = ContactSheet {
+                columns,
+                rows,
+                thumbnail_size: thumb_size,
+                spacing,
+                background,
+                filename_captions: !no_filenames,
+                exif_captions: exif,
+                caption_size,
+                caption_color,
+                font,
+            };
+            process_contact_sheet(input, output, recursive, sheet, quality, format, threads)?;
+        }
     }
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_resize(
     input: std::path::PathBuf,
     output: Option<std::path::PathBuf>,
@@ -99,63 +193,126 @@ fn process_resize(
     quality: u8,
     format: Option<OutputFormat>,
     keep_aspect: bool,
-    strip_metadata: bool,
-    algorithm: Algorithm,
+    metadata: MetadataArgs,
+    redact: RedactArgs,
+    target: TargetArgs,
+    resample: ResampleArgs,
+    adjust: AdjustArgs,
+    watermark: WatermarkArgs,
+    text: TextArgs,
+    size: SizeArgs,
+    fit: FitArgs,
+    resize_policy: CliResizePolicy,
     progressive: bool,
     max_file_size: Option<u64>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    use crate::utils::generate_output_path;
-    
     let output_path = generate_output_path(&input, output.as_deref(), "resized");
+    require_format_for_stdout(&output_path, format.as_ref())?;
 
     let config = ProcessConfig {
         width,
         height,
         scale,
+        longest_edge: size.longest_edge.unwrap_or(0),
+        shortest_edge: size.shortest_edge.unwrap_or(0),
+        max_pixels: size.max_pixels.unwrap_or(0),
         quality,
         keep_aspect,
-        strip_metadata,
-        algorithm: algorithm.into(),
+        resize_policy: resize_policy.into(),
+        fit: fit.fit.clone().into(),
+        gravity: fit.gravity(),
+        background: fit.background,
+        strip_metadata: metadata.strip_metadata,
+        metadata_rules: metadata.rules(),
+        auto_orient: !metadata.no_auto_orient,
+        convert_to_srgb: metadata.to_srgb,
+        embed_icc_profile: !metadata.no_icc,
+        redaction: redact.redaction(),
+        algorithm: resample.algorithm.clone().into(),
+        linear_light: resample.linear_light(),
+        adjustments: adjust.adjustments(),
+        sharpen: resample.sharpen,
+        watermark: watermark.watermark(),
+        text_overlay: text.text_overlay(),
         max_file_size,
+        target_size: target.target_size,
+        downscale_to_target: target.allow_downscale,
+        target_ssim: target.target_ssim,
         format: format.map(|f| f.into()),
+        progressive_jpeg: progressive,
         ..Default::default()
     };
 
     config.validate()?;
 
     let processor = ImageProcessor::new(config);
-    let stats = processor.process(&input, &output_path)?;
+    let stats = process_file_or_stream(&processor, &input, &output_path)?;
 
-    println!("✓ Resized image saved to: {}", output_path.display());
-    print_stats(&stats);
+    report_saved("Resized", &output_path, &stats);
 
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_batch(
     input: std::path::PathBuf,
     output: std::path::PathBuf,
-    width: u32,
+    width: Option<u32>,
     height: u32,
     format: Option<OutputFormat>,
     quality: u8,
     threads: usize,
     recursive: bool,
-    strip_metadata: bool,
-    algorithm: Algorithm,
+    metadata: MetadataArgs,
+    redact: RedactArgs,
+    target: TargetArgs,
+    transform: TransformArgs,
+    resample: ResampleArgs,
+    adjust: AdjustArgs,
+    watermark: WatermarkArgs,
+    text: TextArgs,
+    size: SizeArgs,
+    fit: FitArgs,
+    resize_policy: CliResizePolicy,
     no_png_optimize: bool,
     max_file_size: Option<u64>,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    // The default width only applies when no other size was asked for
+    let default_width = if size.is_set() { 0 } else { 800 };
+
     let config = ProcessConfig {
-        width,
+        width: width.unwrap_or(default_width),
         height,
         scale: 0.0,
+        longest_edge: size.longest_edge.unwrap_or(0),
+        shortest_edge: size.shortest_edge.unwrap_or(0),
+        max_pixels: size.max_pixels.unwrap_or(0),
         quality,
         keep_aspect: true,
-        strip_metadata,
-        algorithm: algorithm.into(),
+        resize_policy: resize_policy.into(),
+        fit: fit.fit.clone().into(),
+        gravity: fit.gravity(),
+        background: fit.background,
+        strip_metadata: metadata.strip_metadata,
+        metadata_rules: metadata.rules(),
+        auto_orient: !metadata.no_auto_orient,
+        convert_to_srgb: metadata.to_srgb,
+        embed_icc_profile: !metadata.no_icc,
+        redaction: redact.redaction(),
+        algorithm: resample.algorithm.clone().into(),
+        linear_light: resample.linear_light(),
+        transforms: transform.transforms(),
+        adjustments: adjust.adjustments(),
+        sharpen: resample.sharpen,
+        watermark: watermark.watermark(),
+        text_overlay: text.text_overlay(),
         max_file_size,
+        target_size: target.target_size,
+        downscale_to_target: target.allow_downscale,
+        target_ssim: target.target_ssim,
         format: format.map(|f| f.into()),
+        optimize_png: !no_png_optimize,
+        ..Default::default()
     };
 
     config.validate()?;
@@ -166,7 +323,7 @@ fn process_batch(
     let stats = processor.process_directory(&input, &output, recursive)?;
 
     println!("✓ Batch processing complete.");
-    print_stats(&stats);
+    print!("{}", format_stats(&stats));
 
     if !stats.errors.is_empty() {
         println!("\n⚠  Errors encountered:");
@@ -178,18 +335,21 @@ fn process_batch(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_optimize(
     input: std::path::PathBuf,
     output: Option<std::path::PathBuf>,
     quality: u8,
-    strip_metadata: bool,
+    format: Option<OutputFormat>,
+    metadata: MetadataArgs,
+    redact: RedactArgs,
+    target: TargetArgs,
     progressive: bool,
     no_png_optimize: bool,
     max_file_size: Option<u64>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    use crate::utils::generate_output_path;
-    
     let output_path = generate_output_path(&input, output.as_deref(), "optimized");
+    require_format_for_stdout(&output_path, format.as_ref())?;
 
     let config = ProcessConfig {
         width: 0,
@@ -197,19 +357,29 @@ fn process_optimize(
         scale: 0.0,
         quality,
         keep_aspect: true,
-        strip_metadata,
+        strip_metadata: metadata.strip_metadata,
+        metadata_rules: metadata.rules(),
+        auto_orient: !metadata.no_auto_orient,
+        convert_to_srgb: metadata.to_srgb,
+        embed_icc_profile: !metadata.no_icc,
+        redaction: redact.redaction(),
         algorithm: ResizeAlgorithm::Lanczos3,
         max_file_size,
-        format: None,
+        target_size: target.target_size,
+        downscale_to_target: target.allow_downscale,
+        target_ssim: target.target_ssim,
+        format: format.map(|f| f.into()),
+        optimize_png: !no_png_optimize,
+        progressive_jpeg: progressive,
+        ..Default::default()
     };
 
     config.validate()?;
 
     let processor = ImageProcessor::new(config);
-    let stats = processor.process(&input, &output_path)?;
+    let stats = process_file_or_stream(&processor, &input, &output_path)?;
 
-    println!("✓ Optimized image saved to: {}", output_path.display());
-    print_stats(&stats);
+    report_saved("Optimized", &output_path, &stats);
 
     Ok(())
 }
@@ -218,8 +388,6 @@ fn process_info(
     input: std::path::PathBuf,
     exif: bool,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    use crate::utils::{format_file_size, get_image_info};
-    
     if !input.exists() {
         return Err(format!("File does not exist: {}", input.display()).into());
     }
@@ -245,17 +413,18 @@ fn process_info(
     Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 fn process_convert(
     input: std::path::PathBuf,
     output: Option<std::path::PathBuf>,
     format: OutputFormat,
     quality: u8,
-    strip_metadata: bool,
+    metadata: MetadataArgs,
+    target: TargetArgs,
     max_file_size: Option<u64>,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    use crate::utils::generate_output_path;
-    
     let output_path = generate_output_path(&input, output.as_deref(), "converted");
+    require_format_for_stdout(&output_path, Some(&format))?;
 
     let config = ProcessConfig {
         width: 0,
@@ -263,24 +432,249 @@ fn process_convert(
         scale: 0.0,
         quality,
         keep_aspect: true,
-        strip_metadata,
+        strip_metadata: metadata.strip_metadata,
+        metadata_rules: metadata.rules(),
+        auto_orient: !metadata.no_auto_orient,
+        convert_to_srgb: metadata.to_srgb,
+        embed_icc_profile: !metadata.no_icc,
         algorithm: ResizeAlgorithm::Lanczos3,
         max_file_size,
+        target_size: target.target_size,
+        downscale_to_target: target.allow_downscale,
+        target_ssim: target.target_ssim,
         format: Some(format.into()),
+        ..Default::default()
+    };
+
+    config.validate()?;
+
+    let processor = ImageProcessor::new(config);
+    let stats = process_file_or_stream(&processor, &input, &output_path)?;
+
+    report_saved("Converted", &output_path, &stats);
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+fn process_responsive(
+    input: std::path::PathBuf,
+    output: std::path::PathBuf,
+    spec: ResponsiveSpec,
+    quality: u8,
+    metadata: MetadataArgs,
+    resample: ResampleArgs,
+    html: std::path::PathBuf,
+    manifest: Option<std::path::PathBuf>,
+    sizes: &str,
+    alt: &str,
+    url_prefix: &str,
+    max_file_size: Option<u64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if !input.is_file() {
+        return Err(format!("Input file does not exist: {}", input.display()).into());
+    }
+    if let Some(max_size) = max_file_size {
+        if std::fs::metadata(&input)?.len() > max_size {
+            return Err(format!("File size exceeds limit {}", max_size).into());
+        }
+    }
+
+    let config = ProcessConfig {
+        quality,
+        strip_metadata: metadata.strip_metadata,
+        metadata_rules: metadata.rules(),
+        auto_orient: !metadata.no_auto_orient,
+        convert_to_srgb: metadata.to_srgb,
+        embed_icc_profile: !metadata.no_icc,
+        algorithm: resample.algorithm.clone().into(),
+        linear_light: resample.linear_light(),
+        sharpen: resample.sharpen,
+        max_file_size,
+        ..Default::default()
+    };
+
+    config.validate()?;
+    spec.validate()?;
+
+    let set = ResponsiveGenerator::new(config, spec).generate(&input, &output)?;
+
+    // The snippet and manifest may go to stdout, so the summary goes to stderr
+    let total: u64 = set.variants.iter().map(|variant| variant.bytes).sum();
+    eprintln!("✓ Wrote {} variant(s) to: {}", set.variants.len(), output.display());
+    for variant in &set.variants {
+        eprintln!(
+            "  {} ({}x{}, {})",
+            variant.file, variant.width, variant.height, format_file_size(variant.bytes)
+        );
+    }
+    eprintln!("  Total size: {}", format_file_size(total));
+
+    let snippet = set.picture_html(url_prefix, sizes, alt);
+    if is_stdio_path(&html) {
+        print!("{}", snippet);
+    } else {
+        std::fs::write(&html, snippet)?;
+        eprintln!("  HTML snippet: {}", html.display());
+    }
+
+    let manifest_path = manifest.unwrap_or_else(|| output.join(format!("{}.json", set.name)));
+    if is_stdio_path(&manifest_path) {
+        println!("{}", set.manifest()?);
+    } else {
+        std::fs::write(&manifest_path, set.manifest()?)?;
+        eprintln!("  Manifest: {}", manifest_path.display());
+    }
+
+    Ok(())
+}
+
+fn process_contact_sheet(
+    input: std::path::PathBuf,
+    output: std::path::PathBuf,
+    recursive: bool,
+    sheet: ContactSheet,
+    quality: u8,
+    format: Option<OutputFormat>,
+    threads: usize,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let maker = ContactSheetMaker::new(sheet, threads)?;
+    let result = maker.render_directory(&input, recursive)?;
+    if result.pages.is_empty() {
+        return Err(format!("No images could be read from {}", input.display()).into());
+    }
+
+    let image_format = match format {
+        Some(OutputFormat::Jpeg) => Some(image::ImageFormat::Jpeg),
+        Some(OutputFormat::Png) => Some(image::ImageFormat::Png),
+        Some(OutputFormat::WebP) => Some(image::ImageFormat::WebP),
+        Some(OutputFormat::Same) | None => None,
+    };
+    let compressor = Compressor::new(quality);
+    let pages = result.pages.len();
+    let mut paths = Vec::with_capacity(pages);
+    for (i, page) in result.pages.iter().enumerate() {
+        let path = ContactSheetMaker::page_path(&output, i + 1, pages);
+        match image_format {
+            Some(image_format) => compressor.save_with_format(page, &path, image_format)?,
+            None => compressor.save(page, &path)?,
+        }
+        paths.push(path);
+    }
+
+    println!("✓ Contact sheet of {} image(s) on {} page(s):", result.images, pages);
+    for path in &paths {
+        println!("  {}", path.display());
+    }
+
+    if !result.errors.is_empty() {
+        println!("\n⚠  Errors encountered:");
+        for (context, error) in &result.errors {
+            println!("  - {}: {}", context, error);
+        }
+    }
+
+    Ok(())
+}
+
+fn process_transform(
+    input: std::path::PathBuf,
+    output: Option<std::path::PathBuf>,
+    format: Option<OutputFormat>,
+    quality: u8,
+    metadata: MetadataArgs,
+    transform: TransformArgs,
+    max_file_size: Option<u64>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let output_path = generate_output_path(&input, output.as_deref(), "transformed");
+    require_format_for_stdout(&output_path, format.as_ref())?;
+
+    let transforms = transform.transforms();
+    if transforms.is_empty() {
+        return Err("Nothing to do: pass --rotate, --flip-horizontal, --flip-vertical, --transpose or --transverse"
+            .into());
+    }
+
+    let config = ProcessConfig {
+        width: 0,
+        height: 0,
+        scale: 0.0,
+        quality,
+        keep_aspect: true,
+        strip_metadata: metadata.strip_metadata,
+        metadata_rules: metadata.rules(),
+        auto_orient: !metadata.no_auto_orient,
+        convert_to_srgb: metadata.to_srgb,
+        embed_icc_profile: !metadata.no_icc,
+        transforms,
+        max_file_size,
+        format: format.map(|f| f.into()),
+        ..Default::default()
     };
 
     config.validate()?;
 
     let processor = ImageProcessor::new(config);
-    let stats = processor.process(&input, &output_path)?;
+    let stats = process_file_or_stream(&processor, &input, &output_path)?;
+
+    report_saved("Transformed", &output_path, &stats);
+
+    Ok(())
+}
 
-    println!("✓ Converted image saved to: {}", output_path.display());
-    print_stats(&stats);
+/// Runs `processor`, treating `-` as stdin for the input and stdout for the output.
+fn process_file_or_stream(
+    processor: &ImageProcessor,
+    input: &Path,
+    output: &Path,
+) -> Result<ProcessingStats, Box<dyn std::error::Error>> {
+    let stats = match (is_stdio_path(input), is_stdio_path(output)) {
+        (false, false) => processor.process(input, output)?,
+        (true, true) => processor.process_to_writer(std::io::stdin().lock(), std::io::stdout().lock())?,
+        (true, false) => {
+            let (encoded, stats) = processor.process_reader(std::io::stdin().lock())?;
+            std::fs::write(output, encoded)?;
+            stats
+        }
+        (false, true) => {
+            let data = std::fs::read(input)?;
+            let (encoded, stats) = processor.process_bytes(&data)?;
+            let mut stdout = std::io::stdout().lock();
+            stdout.write_all(&encoded)?;
+            stdout.flush()?;
+            stats
+        }
+    };
+
+    Ok(stats)
+}
+
+/// There is no file extension to fall back on when writing to stdout.
+fn require_format_for_stdout(
+    output_path: &Path,
+    format: Option<&OutputFormat>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if is_stdio_path(output_path) && matches!(format, None | Some(OutputFormat::Same)) {
+        return Err("--format must be set to an explicit format when writing to stdout".into());
+    }
 
     Ok(())
 }
 
-fn print_stats(stats: &ProcessingStats) {
+/// Prints the result summary, on stderr when stdout carries the image itself.
+fn report_saved(action: &str, output_path: &Path, stats: &ProcessingStats) {
+    if is_stdio_path(output_path) {
+        eprintln!("✓ {} image written to stdout", action);
+        eprint!("{}", format_stats(stats));
+    } else {
+        println!("✓ {} image saved to: {}", action, output_path.display());
+        print!("{}", format_stats(stats));
+    }
+}
+
+fn format_stats(stats: &ProcessingStats) -> String {
+    let mut output = String::new();
+
     if stats.processed_count > 0 && stats.total_size_before > 0 {
         let reduction = if stats.total_size_after < stats.total_size_before {
             let percent = (stats.total_size_before - stats.total_size_after) as f64 
@@ -290,8 +684,35 @@ fn print_stats(stats: &ProcessingStats) {
             String::new()
         };
         
-        println!("  Processed: {} file(s)", stats.processed_count);
-        println!("  Original size: {}", format_file_size(stats.total_size_before));
-        println!("  Final size: {}{}", format_file_size(stats.total_size_after), reduction);
+        output.push_str(&format!("  Processed: {} file(s)\n", stats.processed_count));
+        output.push_str(&format!("  Original size: {}\n", format_file_size(stats.total_size_before)));
+        output.push_str(&format!("  Final size: {}{}\n", format_file_size(stats.total_size_after), reduction));
+    }
+
+    let qualities = stats.chosen_qualities.iter().map(|(_, quality)| *quality);
+    if let (Some(min), Some(max)) = (qualities.clone().min(), qualities.max()) {
+        if min == max {
+            output.push_str(&format!("  Chosen quality: {}\n", min));
+        } else {
+            output.push_str(&format!("  Chosen quality: {}-{}\n", min, max));
+        }
     }
-}
\ No newline at end of file
+
+    match stats.skipped_resizes.as_slice() {
+        [] => {}
+        [(_, reason)] if stats.processed_count == 1 => {
+            output.push_str(&format!("  Not resized: {}\n", reason));
+        }
+        skipped => {
+            output.push_str(&format!("  Left at original size: {} file(s)\n", skipped.len()));
+        }
+    }
+
+    if !stats.quality_scores.is_empty() {
+        let mean = stats.quality_scores.iter().map(|(_, score)| score).sum::<f64>()
+            / stats.quality_scores.len() as f64;
+        output.push_str(&format!("  SSIM: {:.4}\n", mean));
+    }
+
+    output
+}
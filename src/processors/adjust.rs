@@ -0,0 +1,112 @@
+// pixie/src/processors/adjust.rs
+use crate::core::{Adjustments, Operation, PipelineContext, ProcessConfig, Result};
+use image::DynamicImage;
+use rayon::prelude::*;
+use super::resizer::{linear_to_srgb, srgb_to_linear};
+use super::Resizer;
+
+/// Applies [`Adjustments`] to images.
+///
+/// Brightness, contrast and gamma work on the sRGB-encoded values, which are close to
+/// perceptually even; saturation and hue work in Oklab, so rotating the hue or
+/// removing color keeps the perceived lightness.
+pub struct Adjuster {
+    adjustments: Adjustments,
+}
+
+impl Adjuster {
+    pub fn new(adjustments: Adjustments) -> Self {
+        Self { adjustments }
+    }
+
+    /// Adjusts the color channels of `image`, keeping its color type; alpha is untouched.
+    pub fn apply(&self, image: &DynamicImage) -> DynamicImage {
+        if self.adjustments.is_identity() {
+            return image.clone();
+        }
+
+        let has_alpha = image.color().has_alpha();
+        let mut buffer = image.to_rgba32f();
+        buffer.par_chunks_mut(4).for_each(|pixel| {
+            let rgb = self.adjust_pixel([pixel[0], pixel[1], pixel[2]]);
+            pixel[..3].copy_from_slice(&rgb);
+        });
+
+        let adjusted = if has_alpha {
+            DynamicImage::ImageRgba32F(buffer)
+        } else {
+            DynamicImage::ImageRgb32F(DynamicImage::ImageRgba32F(buffer).to_rgb32f())
+        };
+        Resizer::restore_color_type(adjusted, image.color())
+    }
+
+    fn adjust_pixel(&self, rgb: [f32; 3]) -> [f32; 3] {
+        let Adjustments { brightness, contrast, saturation, gamma, hue } = self.adjustments;
+
+        let tone = |value: f32| {
+            let value = (value - 0.5) * (1.0 + contrast) + 0.5 + brightness;
+            value.clamp(0.0, 1.0).powf(1.0 / gamma)
+        };
+        let rgb = rgb.map(tone);
+
+        if saturation == 0.0 && hue == 0.0 {
+            return rgb;
+        }
+
+        let [l, a, b] = linear_to_oklab(rgb.map(srgb_to_linear));
+        let (sin, cos) = hue.to_radians().sin_cos();
+        let chroma = 1.0 + saturation;
+        let (a, b) = ((a * cos - b * sin) * chroma, (a * sin + b * cos) * chroma);
+        oklab_to_linear([l, a, b]).map(linear_to_srgb)
+    }
+}
+
+fn linear_to_oklab([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let l = (0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b).cbrt();
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+fn oklab_to_linear([l, a, b]: [f32; 3]) -> [f32; 3] {
+    let l_ = (l + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m_ = (l - 0.105_561_346 * a - 0.063_854_17 * b).powi(3);
+    let s_ = (l - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+    [
+        4.076_741_7 * l_ - 3.307_711_6 * m_ + 0.230_969_94 * s_,
+        -1.268_438 * l_ + 2.609_757_4 * m_ - 0.341_319_38 * s_,
+        -0.004_196_086_3 * l_ - 0.703_418_6 * m_ + 1.707_614_7 * s_,
+    ]
+}
+
+/// Applies brightness, contrast, saturation, gamma and hue adjustments.
+pub struct AdjustOperation {
+    adjuster: Adjuster,
+}
+
+impl AdjustOperation {
+    pub fn new(adjustments: Adjustments) -> Self {
+        Self {
+            adjuster: Adjuster::new(adjustments),
+        }
+    }
+
+    /// Returns `None` when `config` leaves the colors alone.
+    pub fn from_config(config: &ProcessConfig) -> Option<Self> {
+        (!config.adjustments.is_identity()).then(|| Self::new(config.adjustments))
+    }
+}
+
+impl Operation for AdjustOperation {
+    fn name(&self) -> &str {
+        "adjust"
+    }
+
+    fn apply(&self, image: DynamicImage, _context: &mut PipelineContext) -> Result<DynamicImage> {
+        Ok(self.adjuster.apply(&image))
+    }
+}
@@ -1,5 +1,5 @@
-use crate::core::{ImageToolError, ProcessConfig, Result, ProcessingStats};
-use crate::processors::prelude::*;
+use crate::core::{ImageToolError, ProcessConfig, Result, ProcessingStats, Pipeline};
+use crate::core::processor::ImageProcessor;
 use indicatif::{ParallelProgressIterator, ProgressBar, ProgressStyle};
 use rayon::prelude::*;
 use std::path::{Path, PathBuf};
@@ -8,14 +8,17 @@ use walkdir::WalkDir;
 
 pub struct BatchProcessor {
     config: ProcessConfig,
+    pipeline: Arc<Pipeline>,
     max_threads: usize,
     thread_pool: Option<rayon::ThreadPool>,
 }
 
 impl BatchProcessor {
     pub fn new(config: ProcessConfig, max_threads: usize) -> Result<Self> {
+        let pipeline = Pipeline::from_config(&config);
         let mut processor = Self {
             config,
+            pipeline: Arc::new(pipeline),
             max_threads,
             thread_pool: None,
         };
@@ -34,6 +37,16 @@ impl BatchProcessor {
         Ok(processor)
     }
 
+    /// Runs `pipeline` on every image instead of the one derived from the config.
+    pub fn with_pipeline(mut self, pipeline: impl Into<Arc<Pipeline>>) -> Self {
+        self.pipeline = pipeline.into();
+        self
+    }
+
+    pub fn max_threads(&self) -> usize {
+        self.max_threads
+    }
+
     pub fn process_directory(
         &self,
         input_dir: &Path,
@@ -65,36 +78,10 @@ impl BatchProcessor {
         // Process images in parallel
         let config = Arc::new(self.config.clone());
         let output_dir = Arc::new(output_dir.to_path_buf());
-        
-        let results: Vec<Result<ProcessingStats>> = if let Some(pool) = &self.thread_pool {
-            // Use custom thread pool
-            pool.install(|| {
-                image_paths
-                    .par_iter()
-                    .progress_with(pb.clone())
-                    .map(|input_path| {
-                        self.process_single_image_in_batch(
-                            input_path,
-                            &output_dir,
-                            config.as_ref(),
-                        )
-                    })
-                    .collect()
-            })
-        } else {
-            // Use global thread pool
-            image_paths
-                .par_iter()
-                .progress_with(pb.clone())
-                .map(|input_path| {
-                    self.process_single_image_in_batch(
-                        input_path,
-                        &output_dir,
-                        config.as_ref(),
-                    )
-                })
-                .collect()
-        };
+
+        let results = self.run_parallel(&image_paths, &pb, |input_path| {
+            self.process_single_image_in_batch(input_path, &output_dir, config.as_ref())
+        });
 
         // Aggregate results
         let mut stats = ProcessingStats::default();
@@ -104,6 +91,9 @@ impl BatchProcessor {
                     stats.processed_count += image_stats.processed_count;
                     stats.total_size_before += image_stats.total_size_before;
                     stats.total_size_after += image_stats.total_size_after;
+                    stats.chosen_qualities.extend(image_stats.chosen_qualities);
+                    stats.quality_scores.extend(image_stats.quality_scores);
+                    stats.skipped_resizes.extend(image_stats.skipped_resizes);
                 }
                 Err(e) => {
                     stats.errors.push(("Processing error".to_string(), e.to_string()));
@@ -120,6 +110,42 @@ impl BatchProcessor {
         Ok(stats)
     }
 
+    /// Runs `f` on every path in parallel on the batch's thread pool, with a progress bar.
+    ///
+    /// Results keep the order of `paths`. Other tools use this to decode a directory
+    /// the same way `process_directory` does.
+    pub fn map_images<T, F>(&self, paths: &[PathBuf], f: F) -> Vec<Result<T>>
+    where
+        T: Send,
+        F: Fn(&Path) -> Result<T> + Sync,
+    {
+        let pb = self.create_progress_bar(paths.len());
+        let results = self.run_parallel(paths, &pb, f);
+        pb.finish_and_clear();
+        results
+    }
+
+    fn run_parallel<T, F>(&self, paths: &[PathBuf], pb: &ProgressBar, f: F) -> Vec<Result<T>>
+    where
+        T: Send,
+        F: Fn(&Path) -> Result<T> + Sync,
+    {
+        let run = || {
+            paths
+                .par_iter()
+                .progress_with(pb.clone())
+                .map(|path| f(path))
+                .collect()
+        };
+
+        match &self.thread_pool {
+            // Use custom thread pool
+            Some(pool) => pool.install(run),
+            // Use global thread pool
+            None => run(),
+        }
+    }
+
     fn process_single_image_in_batch(
         &self,
         input_path: &Path,
@@ -135,12 +161,13 @@ impl BatchProcessor {
 
         let output_path = output_dir.join(file_name);
 
-        // Create processor and process
-        let processor = crate::core::processor::ImageProcessor::new(config.clone());
+        // Every image shares the batch's pipeline
+        let processor = ImageProcessor::with_pipeline(config.clone(), Arc::clone(&self.pipeline));
         processor.process(input_path, &output_path)
     }
 
-    fn collect_image_paths(&self, input_dir: &Path, recursive: bool) -> Result<Vec<PathBuf>> {
+    /// Image files in `input_dir` (and its subdirectories when `recursive`), by extension.
+    pub fn collect_image_paths(&self, input_dir: &Path, recursive: bool) -> Result<Vec<PathBuf>> {
         let walker = if recursive {
             WalkDir::new(input_dir)
         } else {
@@ -188,7 +215,7 @@ impl BatchProcessor {
 
         let savings = (stats.total_size_before as f64 - stats.total_size_after as f64)
             / stats.total_size_before as f64 * 100.0;
-        savings.max(0.0).min(100.0)
+        savings.clamp(0.0, 100.0)
     }
 
     pub fn validate_paths(&self, input_dir: &Path, output_dir: &Path) -> Result<()> {
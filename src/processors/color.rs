@@ -0,0 +1,149 @@
+// pixie/src/processors/color.rs
+use crate::core::{ImageToolError, Operation, PipelineContext, Result};
+use image::DynamicImage;
+use moxcms::{ColorProfile, DataColorSpace, Layout, TransformExecutor, TransformOptions};
+
+/// Converts pixels from an embedded ICC profile to sRGB.
+pub struct ColorConverter {
+    options: TransformOptions,
+}
+
+impl ColorConverter {
+    pub fn new() -> Self {
+        Self {
+            options: TransformOptions::default(),
+        }
+    }
+
+    /// Converts `image` from the color space described by `icc_profile` to sRGB.
+    ///
+    /// 8-bit images stay 8-bit, 16-bit images stay 16-bit and float images stay
+    /// float; grayscale images come back as RGB. Profiles that are not RGB (CMYK,
+    /// Lab, ...) are rejected.
+    pub fn to_srgb(&self, image: &DynamicImage, icc_profile: &[u8]) -> Result<DynamicImage> {
+        let source = ColorProfile::new_from_slice(icc_profile)
+            .map_err(|e| ImageToolError::ProcessingError(format!("Invalid ICC profile: {}", e)))?;
+
+        if source.color_space != DataColorSpace::Rgb {
+            return Err(ImageToolError::UnsupportedFormat(format!(
+                "Cannot convert {:?} ICC profiles to sRGB",
+                source.color_space
+            )));
+        }
+
+        let srgb = ColorProfile::new_srgb();
+        let has_alpha = image.color().has_alpha();
+        let layout = if has_alpha { Layout::Rgba } else { Layout::Rgb };
+        let transform_error =
+            |e: moxcms::CmsError| ImageToolError::ProcessingError(format!("Color conversion failed: {}", e));
+
+        let bits = image.color().bytes_per_pixel() / image.color().channel_count();
+        let converted = match bits {
+            1 => {
+                let transform = source
+                    .create_transform_8bit(layout, &srgb, layout, self.options)
+                    .map_err(transform_error)?;
+                if has_alpha {
+                    let mut buffer = image.to_rgba8();
+                    Self::transform_in_place(transform.as_ref(), &mut buffer).map_err(transform_error)?;
+                    DynamicImage::ImageRgba8(buffer)
+                } else {
+                    let mut buffer = image.to_rgb8();
+                    Self::transform_in_place(transform.as_ref(), &mut buffer).map_err(transform_error)?;
+                    DynamicImage::ImageRgb8(buffer)
+                }
+            }
+            2 => {
+                let transform = source
+                    .create_transform_16bit(layout, &srgb, layout, self.options)
+                    .map_err(transform_error)?;
+                if has_alpha {
+                    let mut buffer = image.to_rgba16();
+                    Self::transform_in_place(transform.as_ref(), &mut buffer).map_err(transform_error)?;
+                    DynamicImage::ImageRgba16(buffer)
+                } else {
+                    let mut buffer = image.to_rgb16();
+                    Self::transform_in_place(transform.as_ref(), &mut buffer).map_err(transform_error)?;
+                    DynamicImage::ImageRgb16(buffer)
+                }
+            }
+            _ => {
+                let transform = source
+                    .create_transform_f32(layout, &srgb, layout, self.options)
+                    .map_err(transform_error)?;
+                if has_alpha {
+                    let mut buffer = image.to_rgba32f();
+                    Self::transform_in_place(transform.as_ref(), &mut buffer).map_err(transform_error)?;
+                    DynamicImage::ImageRgba32F(buffer)
+                } else {
+                    let mut buffer = image.to_rgb32f();
+                    Self::transform_in_place(transform.as_ref(), &mut buffer).map_err(transform_error)?;
+                    DynamicImage::ImageRgb32F(buffer)
+                }
+            }
+        };
+
+        Ok(converted)
+    }
+
+    fn transform_in_place<V, T>(transform: &T, samples: &mut [V]) -> std::result::Result<(), moxcms::CmsError>
+    where
+        V: Copy + Default,
+        T: TransformExecutor<V> + ?Sized,
+    {
+        let source = samples.to_vec();
+        transform.transform(&source, samples)
+    }
+}
+
+impl Default for ColorConverter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Converts the pixels to sRGB using `PipelineContext::icc_profile`, then drops the profile.
+///
+/// Images without a profile are assumed to be sRGB already and pass through untouched.
+/// Profiles that cannot be converted are kept so the output still displays correctly.
+pub struct ConvertToSrgbOperation {
+    converter: ColorConverter,
+}
+
+impl ConvertToSrgbOperation {
+    pub fn new() -> Self {
+        Self {
+            converter: ColorConverter::new(),
+        }
+    }
+}
+
+impl Default for ConvertToSrgbOperation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Operation for ConvertToSrgbOperation {
+    fn name(&self) -> &str {
+        "to-srgb"
+    }
+
+    fn apply(&self, image: DynamicImage, context: &mut PipelineContext) -> Result<DynamicImage> {
+        let Some(icc_profile) = context.icc_profile.as_deref() else {
+            return Ok(image);
+        };
+
+        match self.converter.to_srgb(&image, icc_profile) {
+            Ok(converted) => {
+                log::debug!("Converted pixels to sRGB from embedded ICC profile");
+                context.icc_profile = None;
+                Ok(converted)
+            }
+            Err(e) => {
+                log::warn!("Keeping original ICC profile: {}", e);
+                Ok(image)
+            }
+        }
+    }
+}
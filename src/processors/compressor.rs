@@ -1,12 +1,21 @@
 // pixie/src/processors/compressor.rs
-use crate::core::{ImageToolError, Result};
-use image::{DynamicImage, ImageFormat};
-use image::ImageOutputFormat;
+use crate::core::{ImageToolError, Operation, PipelineContext, ProcessConfig, Result};
+use crate::processors::{ssim, MetadataProcessor, Resizer};
+use image::codecs::jpeg::JpegEncoder;
+use image::codecs::png::PngEncoder;
+use image::codecs::webp::WebPEncoder;
+use image::{DynamicImage, GenericImageView, ImageEncoder, ImageFormat};
 use oxipng::{optimize_from_memory, Options};
-use std::fs::File;
-use std::io::{BufWriter, Cursor};
+use std::io::Cursor;
 use std::path::Path;
 
+/// Lowest quality tried before a size target resorts to downscaling.
+const DOWNSCALE_MIN_QUALITY: u8 = 40;
+
+/// Upper bound on shrink steps when fitting a size target.
+const MAX_DOWNSCALE_STEPS: usize = 16;
+
+#[derive(Clone)]
 pub struct Compressor {
     quality: u8,
     optimize_png: bool,
@@ -22,6 +31,20 @@ impl Compressor {
         }
     }
 
+    pub fn with_quality(mut self, quality: u8) -> Self {
+        self.quality = quality.clamp(1, 100);
+        self
+    }
+
+    pub fn quality(&self) -> u8 {
+        self.quality
+    }
+
+    /// Whether the encoder for `format` honours the quality setting.
+    pub fn uses_quality(format: ImageFormat) -> bool {
+        format == ImageFormat::Jpeg
+    }
+
     pub fn with_png_optimization(mut self, optimize: bool) -> Self {
         self.optimize_png = optimize;
         self
@@ -50,118 +73,147 @@ impl Compressor {
             self.quality
         );
 
-        match format {
-            ImageFormat::Jpeg => self.save_jpeg(image, path),
-            ImageFormat::Png => self.save_png(image, path),
-            ImageFormat::WebP => self.save_webp(image, path),
-            _ => self.save_generic(image, path, format),
-        }
-    }
+        let data = self.compress_to_bytes(image, format)?;
+        std::fs::write(path, data)?;
 
-    fn save_jpeg(&self, image: &DynamicImage, path: &Path) -> Result<()> {
-        let file = File::create(path)?;
-        let writer = BufWriter::new(file);
-
-        let output_format = if self.progressive_jpeg {
-            ImageOutputFormat::JpegProgressive(self.quality)
-        } else {
-            ImageOutputFormat::Jpeg(self.quality)
-        };
-
-        image.write_to(writer, output_format)?;
         self.log_save_result(path)
     }
 
-    fn save_png(&self, image: &DynamicImage, path: &Path) -> Result<()> {
-        if self.optimize_png {
-            // First save to memory
-            let mut buffer = Cursor::new(Vec::new());
-            image.write_to(&mut buffer, ImageOutputFormat::Png)?;
-            
-            // Optimize with oxipng
-            let optimized_data = optimize_from_memory(&buffer.into_inner(), &Options::default())
-                .map_err(|e| ImageToolError::ProcessingError(format!("PNG optimization failed: {}", e)))?;
-            
-            // Write optimized data
-            std::fs::write(path, optimized_data)?;
-        } else {
-            let file = File::create(path)?;
-            let writer = BufWriter::new(file);
-            image.write_to(writer, ImageOutputFormat::Png)?;
-        }
-
-        self.log_save_result(path)
-    }
-
-    fn save_webp(&self, image: &DynamicImage, path: &Path) -> Result<()> {
-        #[cfg(feature = "webp")]
-        {
-            use image::codecs::webp::WebPEncoder;
-            let file = File::create(path)?;
-            let mut writer = BufWriter::new(file);
-            
-            let encoder = WebPEncoder::new_lossy(&mut writer);
-            encoder.encode(
-                image.as_bytes(),
-                image.width(),
-                image.height(),
-                image.color(),
-            )?;
-        }
-        
-        #[cfg(not(feature = "webp"))]
-        {
-            return Err(ImageToolError::UnsupportedFormat(
-                "WebP support requires 'webp' feature flag".to_string()
-            ));
-        }
-
-        self.log_save_result(path)
-    }
-
-    fn save_generic(
+    pub fn compress_to_bytes(
         &self,
         image: &DynamicImage,
-        path: &Path,
         format: ImageFormat,
-    ) -> Result<()> {
-        let file = File::create(path)?;
-        let writer = BufWriter::new(file);
-        image.write_to(writer, ImageOutputFormat::from(format))?;
-        
-        self.log_save_result(path)
+    ) -> Result<Vec<u8>> {
+        self.compress_with_icc_profile(image, format, None)
     }
 
-    pub fn compress_to_bytes(
+    /// Encodes `image`, embedding `icc_profile` where the output format supports it.
+    pub fn compress_with_icc_profile(
         &self,
         image: &DynamicImage,
         format: ImageFormat,
+        icc_profile: Option<&[u8]>,
     ) -> Result<Vec<u8>> {
         let mut buffer = Cursor::new(Vec::new());
 
         match format {
             ImageFormat::Jpeg => {
-                let output_format = if self.progressive_jpeg {
-                    ImageOutputFormat::JpegProgressive(self.quality)
-                } else {
-                    ImageOutputFormat::Jpeg(self.quality)
-                };
-                image.write_to(&mut buffer, output_format)?;
+                if self.progressive_jpeg {
+                    return self.encode_progressive_jpeg(image, icc_profile);
+                }
+                let mut encoder = JpegEncoder::new_with_quality(&mut buffer, self.quality);
+                Self::set_icc_profile(&mut encoder, icc_profile);
+                image.write_with_encoder(encoder)?;
             }
             ImageFormat::Png => {
-                image.write_to(&mut buffer, ImageOutputFormat::Png)?;
+                let mut encoder = PngEncoder::new(&mut buffer);
+                Self::set_icc_profile(&mut encoder, icc_profile);
+                image.write_with_encoder(encoder)?;
                 if self.optimize_png {
                     return self.optimize_png_bytes(&buffer.into_inner());
                 }
             }
+            ImageFormat::WebP => {
+                // The bundled WebP encoder only supports lossless output
+                let mut encoder = WebPEncoder::new_lossless(&mut buffer);
+                Self::set_icc_profile(&mut encoder, icc_profile);
+                image.write_with_encoder(encoder)?;
+            }
             _ => {
-                image.write_to(&mut buffer, ImageOutputFormat::from(format))?;
+                if icc_profile.is_some() {
+                    log::warn!("Cannot embed an ICC profile into {:?} output, skipping", format);
+                }
+                image.write_to(&mut buffer, format)?;
             }
         }
 
         Ok(buffer.into_inner())
     }
 
+    /// `image`'s JPEG encoder only writes baseline files, so progressive output goes
+    /// through `jpeg-encoder`.
+    fn encode_progressive_jpeg(&self, image: &DynamicImage, icc_profile: Option<&[u8]>) -> Result<Vec<u8>> {
+        let encoding_error = |e: jpeg_encoder::EncodingError| {
+            ImageToolError::ProcessingError(format!("JPEG encoding failed: {}", e))
+        };
+        let (width, height) = image.dimensions();
+        let (Ok(width), Ok(height)) = (u16::try_from(width), u16::try_from(height)) else {
+            return Err(ImageToolError::ProcessingError(format!(
+                "JPEG images are limited to 65535x65535 pixels, got {}x{}",
+                width, height
+            )));
+        };
+
+        let mut buffer = Vec::new();
+        let mut encoder = jpeg_encoder::Encoder::new(&mut buffer, self.quality);
+        encoder.set_progressive(true);
+        if let Some(icc_profile) = icc_profile {
+            encoder.add_icc_profile(icc_profile).map_err(encoding_error)?;
+        }
+
+        match image {
+            DynamicImage::ImageLuma8(gray) => {
+                encoder.encode(gray.as_raw(), width, height, jpeg_encoder::ColorType::Luma)
+            }
+            _ => encoder.encode(&image.to_rgb8(), width, height, jpeg_encoder::ColorType::Rgb),
+        }
+        .map_err(encoding_error)?;
+
+        Ok(buffer)
+    }
+
+    fn set_icc_profile<E: ImageEncoder>(encoder: &mut E, icc_profile: Option<&[u8]>) {
+        if let Some(icc_profile) = icc_profile {
+            if let Err(e) = encoder.set_icc_profile(icc_profile.to_vec()) {
+                log::warn!("Cannot embed ICC profile: {}", e);
+            }
+        }
+    }
+
+    /// Finds the lowest quality whose decoded output reaches `target` SSIM against `image`.
+    ///
+    /// Returns the quality and its score; if even quality 100 misses the target, 100 is
+    /// returned with its (lower) score. Formats that ignore quality are not searched.
+    pub fn search_ssim(&self, image: &DynamicImage, format: ImageFormat, target: f64) -> Result<Option<(u8, f64)>> {
+        if !Self::uses_quality(format) {
+            return Ok(None);
+        }
+
+        let score_at = |quality: u8| -> Result<f64> {
+            let encoded = self.clone().with_quality(quality).compress_to_bytes(image, format)?;
+            let decoded = image::load_from_memory_with_format(&encoded, format)?;
+            let score = ssim(image, &decoded)?;
+            log::debug!("Quality {} scores SSIM {:.5}", quality, score);
+            Ok(score)
+        };
+
+        let (mut low, mut high) = (1u8, 100u8);
+        let mut best = None;
+
+        while low <= high {
+            let quality = low + (high - low) / 2;
+            let score = score_at(quality)?;
+
+            if score >= target {
+                best = Some((quality, score));
+                if quality == low {
+                    break;
+                }
+                high = quality - 1;
+            } else {
+                low = quality + 1;
+            }
+        }
+
+        match best {
+            Some(best) => Ok(Some(best)),
+            None => {
+                log::warn!("SSIM target {} is out of reach, using quality 100", target);
+                Ok(Some((100, score_at(100)?)))
+            }
+        }
+    }
+
     fn optimize_png_bytes(&self, data: &[u8]) -> Result<Vec<u8>> {
         optimize_from_memory(data, &Options::default())
             .map_err(|e| ImageToolError::ProcessingError(format!("PNG optimization failed: {}", e)))
@@ -193,4 +245,228 @@ impl Compressor {
         let savings = (original_size as f64 - compressed_size as f64) / original_size as f64 * 100.0;
         savings.max(0.0)
     }
-}
\ No newline at end of file
+}
+
+/// Encodes the image into `PipelineContext::encoded` using the context's output format.
+///
+/// The context's ICC profile, EXIF (with its size tags updated) and XMP are written
+/// into the output. When `PipelineContext::strip_metadata` is set, whatever the encoder
+/// added on its own is scrubbed first.
+///
+/// With a size target, the highest quality whose complete output (metadata
+/// included) fits is chosen and recorded in `PipelineContext::chosen_quality`.
+pub struct CompressOperation {
+    compressor: Compressor,
+    metadata_processor: MetadataProcessor,
+    embed_icc_profile: bool,
+    target_size: Option<u64>,
+    downscale: Option<Resizer>,
+    target_ssim: Option<f64>,
+}
+
+impl CompressOperation {
+    pub fn new(compressor: Compressor) -> Self {
+        Self {
+            compressor,
+            metadata_processor: MetadataProcessor::new(),
+            embed_icc_profile: true,
+            target_size: None,
+            downscale: None,
+            target_ssim: None,
+        }
+    }
+
+    /// Lowers the quality until the output is at most `max_bytes`.
+    pub fn with_target_size(mut self, max_bytes: u64) -> Self {
+        self.target_size = Some(max_bytes);
+        self
+    }
+
+    /// Picks the lowest quality whose output still reaches `target` SSIM.
+    ///
+    /// Combined with a size target, the SSIM quality becomes the upper bound of the size search.
+    pub fn with_target_ssim(mut self, target: f64) -> Self {
+        self.target_ssim = Some(target);
+        self
+    }
+
+    /// Lets the size target shrink the image with `resizer` when quality alone is not enough.
+    pub fn with_downscale(mut self, resizer: Resizer) -> Self {
+        self.downscale = Some(resizer);
+        self
+    }
+
+    /// Whether to embed `PipelineContext::icc_profile` in the output (default: true).
+    pub fn with_icc_profile(mut self, embed: bool) -> Self {
+        self.embed_icc_profile = embed;
+        self
+    }
+
+    pub fn from_config(config: &ProcessConfig) -> Self {
+        let compressor = Compressor::new(config.quality)
+            .with_png_optimization(config.optimize_png)
+            .with_progressive_jpeg(config.progressive_jpeg);
+        let mut operation = Self::new(compressor).with_icc_profile(config.embed_icc_profile);
+
+        if let Some(target) = config.target_ssim {
+            operation = operation.with_target_ssim(target);
+        }
+
+        if let Some(max_bytes) = config.target_size {
+            operation = operation.with_target_size(max_bytes);
+            if config.downscale_to_target {
+                // Same resampling as the resize step; `resize_exact` ignores aspect and policy
+                operation = operation.with_downscale(Resizer::from_config(config));
+            }
+        }
+
+        operation
+    }
+
+    fn encode(&self, compressor: &Compressor, image: &DynamicImage, context: &PipelineContext) -> Result<Vec<u8>> {
+        let format = context.output_format;
+        let mut encoded = compressor.compress_to_bytes(image, format)?;
+
+        if context.strip_metadata {
+            encoded = self.metadata_processor.strip_metadata(&encoded, format)?;
+        }
+
+        if let Some(icc_profile) = context.icc_profile.as_deref().filter(|_| self.embed_icc_profile) {
+            encoded = self.metadata_processor.embed_icc_profile(&encoded, format, icc_profile)?;
+        }
+
+        if let Some(exif) = &context.exif {
+            // Never copied as is, since its thumbnail would show the unprocessed image
+            match self.metadata_processor.update_dimensions(exif, image.width(), image.height()) {
+                Ok(Some(exif)) => encoded = self.metadata_processor.embed_exif(&encoded, format, &exif)?,
+                Ok(None) => {}
+                Err(e) => log::warn!("Dropping EXIF that cannot be rewritten: {}", e),
+            }
+        }
+
+        if let Some(xmp) = &context.xmp {
+            encoded = self.metadata_processor.embed_xmp(&encoded, format, xmp)?;
+        }
+
+        Ok(encoded)
+    }
+
+    /// Binary-searches the highest quality in `min_quality..=compressor quality` that fits.
+    /// Returns the size of the smallest attempt when nothing fits.
+    fn search_quality(
+        &self,
+        compressor: &Compressor,
+        image: &DynamicImage,
+        context: &PipelineContext,
+        max_bytes: u64,
+        min_quality: u8,
+    ) -> Result<std::result::Result<(u8, Vec<u8>), usize>> {
+        let top = compressor.quality();
+
+        if !Compressor::uses_quality(context.output_format) {
+            let encoded = self.encode(compressor, image, context)?;
+            return Ok(if encoded.len() as u64 <= max_bytes {
+                Ok((top, encoded))
+            } else {
+                Err(encoded.len())
+            });
+        }
+
+        let (mut low, mut high) = (min_quality.min(top), top);
+        let mut best = None;
+        let mut smallest = usize::MAX;
+
+        while low <= high {
+            let quality = low + (high - low) / 2;
+            let encoded = self.encode(&compressor.clone().with_quality(quality), image, context)?;
+            log::debug!("Quality {} encodes to {} bytes", quality, encoded.len());
+
+            if encoded.len() as u64 <= max_bytes {
+                best = Some((quality, encoded));
+                low = quality + 1;
+            } else {
+                smallest = smallest.min(encoded.len());
+                if quality == low {
+                    break;
+                }
+                high = quality - 1;
+            }
+        }
+
+        Ok(best.ok_or(smallest))
+    }
+
+    fn fit_to_size(
+        &self,
+        compressor: &Compressor,
+        mut image: DynamicImage,
+        context: &PipelineContext,
+        max_bytes: u64,
+    ) -> Result<(DynamicImage, u8, Vec<u8>)> {
+        let min_quality = if self.downscale.is_some() { DOWNSCALE_MIN_QUALITY } else { 1 };
+
+        for _ in 0..=MAX_DOWNSCALE_STEPS {
+            let smallest = match self.search_quality(compressor, &image, context, max_bytes, min_quality)? {
+                Ok((quality, encoded)) => return Ok((image, quality, encoded)),
+                Err(smallest) => smallest,
+            };
+
+            let (width, height) = image.dimensions();
+            let resizer = match &self.downscale {
+                Some(resizer) if width > 1 || height > 1 => resizer,
+                _ => break,
+            };
+
+            // Encoded size scales roughly with the pixel count
+            let factor = ((max_bytes as f64 / smallest as f64).sqrt() * 0.95).clamp(0.5, 0.95);
+            let new_width = ((width as f64 * factor).round() as u32).max(1);
+            let new_height = ((height as f64 * factor).round() as u32).max(1);
+            log::debug!("Downscaling to {}x{} to reach {} bytes", new_width, new_height, max_bytes);
+            image = resizer.resize_exact(&image, new_width, new_height);
+        }
+
+        Err(ImageToolError::ProcessingError(format!(
+            "Cannot fit output into {} bytes",
+            max_bytes
+        )))
+    }
+}
+
+impl Operation for CompressOperation {
+    fn name(&self) -> &str {
+        "compress"
+    }
+
+    fn apply(&self, image: DynamicImage, context: &mut PipelineContext) -> Result<DynamicImage> {
+        let format = context.output_format;
+        let mut compressor = self.compressor.clone();
+
+        if let Some(target) = self.target_ssim {
+            if let Some((quality, score)) = compressor.search_ssim(&image, format, target)? {
+                log::info!("Chose quality {} for SSIM {:.4} (target {})", quality, score, target);
+                compressor = compressor.with_quality(quality);
+                context.chosen_quality = Some(quality);
+                context.quality_score = Some(score);
+            }
+        }
+
+        let Some(max_bytes) = self.target_size else {
+            context.encoded = Some(self.encode(&compressor, &image, context)?);
+            return Ok(image);
+        };
+
+        let (image, quality, encoded) = self.fit_to_size(&compressor, image, context, max_bytes)?;
+        log::info!("Chose quality {} to fit {} bytes ({} bytes written)", quality, max_bytes, encoded.len());
+
+        // Lossless encoders ignore quality, so there is nothing to report for them
+        if Compressor::uses_quality(format) {
+            if context.chosen_quality != Some(quality) {
+                // The score no longer describes the written output
+                context.quality_score = None;
+            }
+            context.chosen_quality = Some(quality);
+        }
+        context.encoded = Some(encoded);
+        Ok(image)
+    }
+}
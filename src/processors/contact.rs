@@ -0,0 +1,264 @@
+// pixie/src/processors/contact.rs
+use crate::core::{ContactSheet, ImageToolError, ProcessConfig, ResizeAlgorithm, ResizePolicy, Result};
+use ab_glyph::{Font, FontArc, PxScale, ScaleFont};
+use exif::{In, Tag};
+use image::{imageops, DynamicImage, GenericImageView, Rgba, RgbaImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use std::path::{Path, PathBuf};
+use super::{AutoOrientOperation, BatchProcessor, Loader, MetadataProcessor, Resizer, ResizeMode, TextRenderer};
+
+/// Largest page, in pixels, that a sheet may allocate (400 MB as RGBA).
+const MAX_PAGE_PIXELS: u64 = 100_000_000;
+
+/// A decoded thumbnail and the caption lines under it.
+struct Thumbnail {
+    image: DynamicImage,
+    captions: Vec<String>,
+}
+
+/// The pages of a contact sheet and the files that could not be read.
+#[derive(Debug, Default)]
+pub struct ContactSheetPages {
+    pub pages: Vec<DynamicImage>,
+    /// Number of thumbnails placed across all pages.
+    pub images: usize,
+    /// (file, error) for every skipped file.
+    pub errors: Vec<(String, String)>,
+}
+
+/// Lays out thumbnails of many images on paginated grids.
+///
+/// Directories are walked and decoded in parallel by a [`BatchProcessor`].
+pub struct ContactSheetMaker {
+    sheet: ContactSheet,
+    batch: BatchProcessor,
+    font: FontArc,
+    loader: Loader,
+    metadata_processor: MetadataProcessor,
+}
+
+impl ContactSheetMaker {
+    /// Decodes on `max_threads` threads (0 uses every core).
+    pub fn new(sheet: ContactSheet, max_threads: usize) -> Result<Self> {
+        sheet.validate()?;
+        let font = match &sheet.font {
+            Some(path) => TextRenderer::load_font(path)?,
+            None => TextRenderer::bundled_font(),
+        };
+
+        Ok(Self {
+            sheet,
+            batch: BatchProcessor::new(ProcessConfig::default(), max_threads)?,
+            font,
+            loader: Loader::new(),
+            metadata_processor: MetadataProcessor::new(),
+        })
+    }
+
+    /// Renders every image in `input_dir`, ordered by path.
+    pub fn render_directory(&self, input_dir: &Path, recursive: bool) -> Result<ContactSheetPages> {
+        if !input_dir.is_dir() {
+            return Err(ImageToolError::InvalidParameter(format!(
+                "Input directory does not exist: {}",
+                input_dir.display()
+            )));
+        }
+
+        let mut paths = self.batch.collect_image_paths(input_dir, recursive)?;
+        if paths.is_empty() {
+            log::warn!("No image files found in {}", input_dir.display());
+            return Ok(ContactSheetPages::default());
+        }
+        paths.sort();
+
+        log::info!("Making a contact sheet of {} images from {}", paths.len(), input_dir.display());
+        self.render(&paths)
+    }
+
+    /// Renders `paths` in the given order; files that cannot be decoded are skipped.
+    ///
+    /// Fails before decoding anything if a page would be too large.
+    pub fn render(&self, paths: &[PathBuf]) -> Result<ContactSheetPages> {
+        let per_page = match self.sheet.rows {
+            0 => paths.len().max(1),
+            rows => (self.sheet.columns as usize).saturating_mul(rows as usize),
+        };
+        self.page_size(per_page.min(paths.len()))?;
+
+        let mut thumbnails = Vec::new();
+        let mut errors = Vec::new();
+        for (path, result) in paths.iter().zip(self.batch.map_images(paths, |path| self.thumbnail(path))) {
+            match result {
+                Ok(thumbnail) => thumbnails.push(thumbnail),
+                Err(e) => errors.push((path.display().to_string(), e.to_string())),
+            }
+        }
+
+        let pages = thumbnails.chunks(per_page).map(|page| self.page(page)).collect::<Result<_>>()?;
+
+        Ok(ContactSheetPages {
+            pages,
+            images: thumbnails.len(),
+            errors,
+        })
+    }
+
+    /// Output file of page `page` (from 1) out of `pages`: `sheet.jpg` becomes
+    /// `sheet-1.jpg`, `sheet-2.jpg`, ... when there is more than one page.
+    pub fn page_path(output: &Path, page: usize, pages: usize) -> PathBuf {
+        if pages <= 1 {
+            return output.to_path_buf();
+        }
+        let stem = output.file_stem().and_then(|s| s.to_str()).unwrap_or("contact-sheet");
+        let name = match output.extension().and_then(|e| e.to_str()) {
+            Some(extension) => format!("{}-{}.{}", stem, page, extension),
+            None => format!("{}-{}", stem, page),
+        };
+        output.with_file_name(name)
+    }
+
+    fn thumbnail(&self, path: &Path) -> Result<Thumbnail> {
+        let loaded = self.loader.load_with_metadata(path)?;
+        let mut image = loaded.image;
+        if let Some(orientation) = loaded.exif.as_deref().and_then(AutoOrientOperation::orientation) {
+            image.apply_orientation(orientation);
+        }
+
+        let image = Resizer::new(ResizeAlgorithm::Lanczos3, true)
+            .with_policy(ResizePolicy::ShrinkOnly)
+            .resize(&image, ResizeMode::LongestEdge(self.sheet.thumbnail_size));
+
+        let mut captions = Vec::new();
+        if self.sheet.filename_captions {
+            let name = path.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+            captions.push(name.into_owned());
+        }
+        if self.sheet.exif_captions {
+            let (date, exposure) = loaded
+                .exif
+                .as_deref()
+                .and_then(|raw| self.metadata_processor.read_metadata_from_bytes(raw).ok())
+                .map(|exif| self.exif_captions(&exif))
+                .unwrap_or_default();
+            captions.push(date);
+            captions.push(exposure);
+        }
+
+        Ok(Thumbnail { image, captions })
+    }
+
+    /// Capture date and exposure; either is empty when the EXIF does not have it.
+    fn exif_captions(&self, exif: &exif::Exif) -> (String, String) {
+        let date = exif
+            .get_field(Tag::DateTimeOriginal, In::PRIMARY)
+            .map(|f| f.display_value().to_string())
+            .unwrap_or_default();
+        let exposure = self
+            .metadata_processor
+            .get_exposure_info(exif)
+            .map(|(exposure_time, aperture, iso, focal_length)| {
+                format!("{}  {}  ISO {}  {}", exposure_time, aperture, iso, focal_length)
+            })
+            .unwrap_or_default();
+        (date, exposure)
+    }
+
+    fn caption_lines(&self) -> u32 {
+        self.sheet.filename_captions as u32 + 2 * self.sheet.exif_captions as u32
+    }
+
+    fn line_height(&self) -> u32 {
+        let metrics = self.font.as_scaled(PxScale::from(self.sheet.caption_size));
+        (metrics.height() + metrics.line_gap()).ceil() as u32
+    }
+
+    /// Height of the captions under each thumbnail, including the gap above them.
+    fn caption_height(&self) -> u32 {
+        match self.caption_lines() {
+            0 => 0,
+            lines => self.sheet.spacing / 2 + lines * self.line_height(),
+        }
+    }
+
+    /// Size of a page holding `images` thumbnails, or an error if it is too large.
+    fn page_size(&self, images: usize) -> Result<(u32, u32)> {
+        let sheet = &self.sheet;
+        let too_large = || {
+            ImageToolError::InvalidParameter(format!(
+                "A contact sheet page of {} images is too large; use fewer rows or smaller thumbnails",
+                images
+            ))
+        };
+
+        let rows = u32::try_from(images).map_err(|_| too_large())?.div_ceil(sheet.columns);
+        let cell_width = sheet.thumbnail_size.checked_add(sheet.spacing);
+        let cell_height = cell_width.and_then(|cell| cell.checked_add(self.caption_height()));
+        let width = cell_width
+            .and_then(|cell| cell.checked_mul(sheet.columns))
+            .and_then(|grid| grid.checked_add(sheet.spacing));
+        let height = cell_height
+            .and_then(|cell| cell.checked_mul(rows))
+            .and_then(|grid| grid.checked_add(sheet.spacing));
+
+        match (width, height) {
+            (Some(width), Some(height)) if width as u64 * height as u64 <= MAX_PAGE_PIXELS => Ok((width, height)),
+            _ => Err(too_large()),
+        }
+    }
+
+    fn page(&self, thumbnails: &[Thumbnail]) -> Result<DynamicImage> {
+        let sheet = &self.sheet;
+        let scale = PxScale::from(sheet.caption_size);
+        let line_height = self.line_height();
+        let caption_height = self.caption_height();
+
+        let cell = sheet.thumbnail_size;
+        let (width, height) = self.page_size(thumbnails.len())?;
+        let mut canvas = RgbaImage::from_pixel(width, height, Rgba(sheet.background));
+
+        for (i, thumbnail) in thumbnails.iter().enumerate() {
+            let (column, row) = (i as u32 % sheet.columns, i as u32 / sheet.columns);
+            let x = sheet.spacing + column * (cell + sheet.spacing);
+            let y = sheet.spacing + row * (cell + caption_height + sheet.spacing);
+
+            // Centered in its cell
+            let (thumb_width, thumb_height) = thumbnail.image.dimensions();
+            let thumb_x = x + (cell - thumb_width) / 2;
+            let thumb_y = y + (cell - thumb_height) / 2;
+            imageops::overlay(&mut canvas, &thumbnail.image.to_rgba8(), thumb_x as i64, thumb_y as i64);
+
+            let mut line_y = y + cell + sheet.spacing / 2;
+            for caption in &thumbnail.captions {
+                let text = self.fit(caption, scale, cell);
+                let text_width = text_size(scale, &self.font, &text).0.min(cell);
+                let text_x = x + (cell - text_width) / 2;
+                let color = Rgba(sheet.caption_color);
+                draw_text_mut(&mut canvas, color, text_x as i32, line_y as i32, scale, &self.font, &text);
+                line_y += line_height;
+            }
+        }
+
+        let page = DynamicImage::ImageRgba8(canvas);
+        if sheet.background[3] == 255 {
+            Ok(DynamicImage::ImageRgb8(page.to_rgb8()))
+        } else {
+            Ok(page)
+        }
+    }
+
+    /// `text`, shortened with an ellipsis until it is at most `width` pixels wide.
+    fn fit(&self, text: &str, scale: PxScale, width: u32) -> String {
+        if text_size(scale, &self.font, text).0 <= width {
+            return text.to_string();
+        }
+        let mut chars: Vec<char> = text.chars().collect();
+        while !chars.is_empty() {
+            chars.pop();
+            let shortened = format!("{}…", chars.iter().collect::<String>().trim_end());
+            if text_size(scale, &self.font, &shortened).0 <= width {
+                return shortened;
+            }
+        }
+        String::new()
+    }
+}
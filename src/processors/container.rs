@@ -0,0 +1,434 @@
+// pixie/src/processors/container.rs
+//! Format-aware editing of metadata blocks inside encoded JPEG, PNG and WebP data.
+//!
+//! Only the container structure is touched; compressed image data is copied verbatim.
+use crate::core::{ImageToolError, Result};
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use image::ImageFormat;
+use std::io::Write;
+
+const JPEG_SOI: [u8; 2] = [0xFF, 0xD8];
+const JPEG_APP0: u8 = 0xE0;
+const JPEG_APP1: u8 = 0xE1;
+const JPEG_APP2: u8 = 0xE2;
+const JPEG_APP14: u8 = 0xEE;
+const JPEG_SOS: u8 = 0xDA;
+const JPEG_EOI: u8 = 0xD9;
+const JPEG_COM: u8 = 0xFE;
+const JPEG_MAX_SEGMENT_PAYLOAD: usize = 0xFFFF - 2;
+
+const EXIF_HEADER: &[u8] = b"Exif\0\0";
+const XMP_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const ICC_HEADER: &[u8] = b"ICC_PROFILE\0";
+const PNG_ICC_KEYWORD: &[u8] = b"ICC Profile";
+const PNG_XMP_KEYWORD: &[u8] = b"XML:com.adobe.xmp";
+
+const PNG_SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+const PNG_METADATA_CHUNKS: [&[u8; 4]; 6] = [b"eXIf", b"iTXt", b"tEXt", b"zTXt", b"iCCP", b"tIME"];
+
+const WEBP_METADATA_CHUNKS: [&[u8; 4]; 3] = [b"EXIF", b"XMP ", b"ICCP"];
+const WEBP_FLAG_ICC: u8 = 0x20;
+const WEBP_FLAG_ALPHA: u8 = 0x10;
+const WEBP_FLAG_EXIF: u8 = 0x08;
+const WEBP_FLAG_XMP: u8 = 0x04;
+
+/// Removes EXIF, XMP, IPTC, ICC and comment/text blocks from encoded image data.
+///
+/// Formats without metadata support here are returned unchanged.
+pub fn strip(data: &[u8], format: ImageFormat) -> Result<Vec<u8>> {
+    match format {
+        ImageFormat::Jpeg => strip_jpeg(data),
+        ImageFormat::Png => strip_png(data),
+        ImageFormat::WebP => strip_webp(data),
+        _ => {
+            log::debug!("Metadata stripping is not implemented for {:?}, leaving output as encoded", format);
+            Ok(data.to_vec())
+        }
+    }
+}
+
+/// Embeds a raw EXIF block (TIFF structure) into encoded image data.
+pub fn embed_exif(data: &[u8], format: ImageFormat, exif: &[u8]) -> Result<Vec<u8>> {
+    match format {
+        ImageFormat::Jpeg => {
+            let mut payload = EXIF_HEADER.to_vec();
+            payload.extend_from_slice(exif);
+            insert_jpeg_segment(data, JPEG_APP1, &payload)
+        }
+        ImageFormat::Png => insert_png_chunk(data, b"eXIf", exif),
+        ImageFormat::WebP => insert_webp_chunk(data, b"EXIF", exif, WEBP_FLAG_EXIF),
+        _ => {
+            log::warn!("Cannot embed EXIF metadata into {:?} output, skipping", format);
+            Ok(data.to_vec())
+        }
+    }
+}
+
+/// Embeds a raw XMP packet into encoded image data.
+pub fn embed_xmp(data: &[u8], format: ImageFormat, xmp: &[u8]) -> Result<Vec<u8>> {
+    match format {
+        ImageFormat::Jpeg => {
+            let mut payload = XMP_HEADER.to_vec();
+            payload.extend_from_slice(xmp);
+            insert_jpeg_segment(data, JPEG_APP1, &payload)
+        }
+        ImageFormat::Png => {
+            // iTXt: keyword, then uncompressed, no language tag, no translated keyword
+            let mut payload = PNG_XMP_KEYWORD.to_vec();
+            payload.extend_from_slice(&[0, 0, 0, 0, 0]);
+            payload.extend_from_slice(xmp);
+            insert_png_chunk(data, b"iTXt", &payload)
+        }
+        ImageFormat::WebP => insert_webp_chunk(data, b"XMP ", xmp, WEBP_FLAG_XMP),
+        _ => {
+            log::warn!("Cannot embed XMP metadata into {:?} output, skipping", format);
+            Ok(data.to_vec())
+        }
+    }
+}
+
+/// Embeds an ICC color profile into encoded image data.
+pub fn embed_icc_profile(data: &[u8], format: ImageFormat, icc_profile: &[u8]) -> Result<Vec<u8>> {
+    match format {
+        ImageFormat::Jpeg => {
+            // Split across APP2 segments, each tagged with its sequence number and the count
+            let chunk_size = JPEG_MAX_SEGMENT_PAYLOAD - ICC_HEADER.len() - 2;
+            let chunks: Vec<&[u8]> = icc_profile.chunks(chunk_size).collect();
+            if chunks.len() > 255 {
+                return Err(ImageToolError::ProcessingError(format!(
+                    "ICC profile of {} bytes does not fit in a JPEG",
+                    icc_profile.len()
+                )));
+            }
+
+            // Each segment goes in front of the previous one, so insert the last first
+            let mut output = data.to_vec();
+            for (i, chunk) in chunks.iter().enumerate().rev() {
+                let mut payload = ICC_HEADER.to_vec();
+                payload.extend_from_slice(&[i as u8 + 1, chunks.len() as u8]);
+                payload.extend_from_slice(chunk);
+                output = insert_jpeg_segment(&output, JPEG_APP2, &payload)?;
+            }
+            Ok(output)
+        }
+        ImageFormat::Png => {
+            // iCCP: keyword, then zlib compression of the profile
+            let mut payload = PNG_ICC_KEYWORD.to_vec();
+            payload.extend_from_slice(&[0, 0]);
+            let mut encoder = ZlibEncoder::new(payload, Compression::default());
+            encoder.write_all(icc_profile)?;
+            insert_png_chunk(data, b"iCCP", &encoder.finish()?)
+        }
+        ImageFormat::WebP => insert_webp_chunk(data, b"ICCP", icc_profile, WEBP_FLAG_ICC),
+        _ => {
+            log::warn!("Cannot embed an ICC profile into {:?} output, skipping", format);
+            Ok(data.to_vec())
+        }
+    }
+}
+
+fn invalid(format: &str) -> ImageToolError {
+    ImageToolError::ProcessingError(format!("Malformed {} data", format))
+}
+
+// --- JPEG -------------------------------------------------------------------
+
+/// A marker segment: `(marker, start, end)` where `data[start..end]` includes the marker bytes.
+type JpegSegment = (u8, usize, usize);
+
+/// Splits the header of a JPEG into marker segments up to (not including) SOS.
+/// Returns the segments and the offset where the scan data begins.
+fn jpeg_segments(data: &[u8]) -> Result<(Vec<JpegSegment>, usize)> {
+    if !data.starts_with(&JPEG_SOI) {
+        return Err(invalid("JPEG"));
+    }
+
+    let mut segments = Vec::new();
+    let mut pos = JPEG_SOI.len();
+
+    loop {
+        if pos + 1 >= data.len() || data[pos] != 0xFF {
+            return Err(invalid("JPEG"));
+        }
+
+        // Skip fill bytes
+        let start = pos;
+        while pos < data.len() && data[pos] == 0xFF {
+            pos += 1;
+        }
+        let marker = *data.get(pos).ok_or_else(|| invalid("JPEG"))?;
+        pos += 1;
+
+        if marker == JPEG_SOS || marker == JPEG_EOI {
+            return Ok((segments, start));
+        }
+
+        // Standalone markers (RSTn, TEM) carry no length
+        if (0xD0..=0xD7).contains(&marker) || marker == 0x01 {
+            segments.push((marker, start, pos));
+            continue;
+        }
+
+        if pos + 2 > data.len() {
+            return Err(invalid("JPEG"));
+        }
+        let length = u16::from_be_bytes([data[pos], data[pos + 1]]) as usize;
+        let end = pos + length;
+        if length < 2 || end > data.len() {
+            return Err(invalid("JPEG"));
+        }
+
+        segments.push((marker, start, end));
+        pos = end;
+    }
+}
+
+fn is_jpeg_metadata_marker(marker: u8) -> bool {
+    // APP0 (JFIF) and APP14 (Adobe color transform) affect decoding and are kept
+    let is_app = (JPEG_APP1..=0xEF).contains(&marker) && marker != JPEG_APP14;
+    is_app || marker == JPEG_COM
+}
+
+fn strip_jpeg(data: &[u8]) -> Result<Vec<u8>> {
+    let (segments, scan_start) = jpeg_segments(data)?;
+
+    let mut output = Vec::with_capacity(data.len());
+    output.extend_from_slice(&JPEG_SOI);
+    for (marker, start, end) in segments {
+        if !is_jpeg_metadata_marker(marker) {
+            output.extend_from_slice(&data[start..end]);
+        }
+    }
+    output.extend_from_slice(&data[scan_start..]);
+
+    Ok(output)
+}
+
+/// Inserts an application segment after SOI and any leading APP0/APP1 segments.
+fn insert_jpeg_segment(data: &[u8], marker: u8, payload: &[u8]) -> Result<Vec<u8>> {
+    if payload.len() > JPEG_MAX_SEGMENT_PAYLOAD {
+        return Err(ImageToolError::ProcessingError(format!(
+            "Metadata block of {} bytes does not fit in a JPEG segment",
+            payload.len()
+        )));
+    }
+
+    let (segments, _) = jpeg_segments(data)?;
+    let insert_at = segments
+        .iter()
+        .take_while(|(m, _, _)| *m == JPEG_APP0 || *m == JPEG_APP1)
+        .last()
+        .map_or(JPEG_SOI.len(), |(_, _, end)| *end);
+
+    let mut output = Vec::with_capacity(data.len() + payload.len() + 4);
+    output.extend_from_slice(&data[..insert_at]);
+    output.extend_from_slice(&[0xFF, marker]);
+    output.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+    output.extend_from_slice(payload);
+    output.extend_from_slice(&data[insert_at..]);
+
+    Ok(output)
+}
+
+// --- PNG --------------------------------------------------------------------
+
+/// Returns `(type, start, end)` for every chunk; `data[start..end]` is the whole chunk.
+fn png_chunks(data: &[u8]) -> Result<Vec<([u8; 4], usize, usize)>> {
+    if !data.starts_with(&PNG_SIGNATURE) {
+        return Err(invalid("PNG"));
+    }
+
+    let mut chunks = Vec::new();
+    let mut pos = PNG_SIGNATURE.len();
+    while pos < data.len() {
+        if pos + 8 > data.len() {
+            return Err(invalid("PNG"));
+        }
+        let length = u32::from_be_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]]) as usize;
+        let chunk_type = [data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]];
+        let end = pos + 12 + length;
+        if end > data.len() {
+            return Err(invalid("PNG"));
+        }
+        chunks.push((chunk_type, pos, end));
+        pos = end;
+    }
+
+    Ok(chunks)
+}
+
+fn strip_png(data: &[u8]) -> Result<Vec<u8>> {
+    let mut output = PNG_SIGNATURE.to_vec();
+    for (chunk_type, start, end) in png_chunks(data)? {
+        if !PNG_METADATA_CHUNKS.contains(&&chunk_type) {
+            output.extend_from_slice(&data[start..end]);
+        }
+    }
+
+    Ok(output)
+}
+
+/// Inserts a chunk right before the first image data chunk, as required for metadata.
+fn insert_png_chunk(data: &[u8], chunk_type: &[u8; 4], payload: &[u8]) -> Result<Vec<u8>> {
+    let chunks = png_chunks(data)?;
+    let insert_at = chunks
+        .iter()
+        .find(|(t, _, _)| t == b"IDAT" || t == b"PLTE")
+        .map(|(_, start, _)| *start)
+        .ok_or_else(|| invalid("PNG"))?;
+
+    let mut chunk = Vec::with_capacity(payload.len() + 12);
+    chunk.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+    chunk.extend_from_slice(chunk_type);
+    chunk.extend_from_slice(payload);
+    let crc = crc32(&chunk[4..]);
+    chunk.extend_from_slice(&crc.to_be_bytes());
+
+    let mut output = Vec::with_capacity(data.len() + chunk.len());
+    output.extend_from_slice(&data[..insert_at]);
+    output.extend_from_slice(&chunk);
+    output.extend_from_slice(&data[insert_at..]);
+
+    Ok(output)
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+    !crc
+}
+
+// --- WebP -------------------------------------------------------------------
+
+struct WebpChunk<'a> {
+    fourcc: [u8; 4],
+    payload: &'a [u8],
+}
+
+fn webp_chunks(data: &[u8]) -> Result<Vec<WebpChunk<'_>>> {
+    if data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return Err(invalid("WebP"));
+    }
+
+    let mut chunks = Vec::new();
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let fourcc = [data[pos], data[pos + 1], data[pos + 2], data[pos + 3]];
+        let size = u32::from_le_bytes([data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]]) as usize;
+        let start = pos + 8;
+        let end = start + size;
+        if end > data.len() {
+            return Err(invalid("WebP"));
+        }
+        chunks.push(WebpChunk { fourcc, payload: &data[start..end] });
+        // Chunks are padded to an even size
+        pos = end + (size & 1);
+    }
+
+    Ok(chunks)
+}
+
+fn write_webp(chunks: &[WebpChunk<'_>]) -> Vec<u8> {
+    let mut body = b"WEBP".to_vec();
+    for chunk in chunks {
+        body.extend_from_slice(&chunk.fourcc);
+        body.extend_from_slice(&(chunk.payload.len() as u32).to_le_bytes());
+        body.extend_from_slice(chunk.payload);
+        if chunk.payload.len() & 1 == 1 {
+            body.push(0);
+        }
+    }
+
+    let mut output = b"RIFF".to_vec();
+    output.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    output.extend_from_slice(&body);
+    output
+}
+
+fn strip_webp(data: &[u8]) -> Result<Vec<u8>> {
+    let mut chunks = webp_chunks(data)?;
+    chunks.retain(|chunk| !WEBP_METADATA_CHUNKS.contains(&&chunk.fourcc));
+
+    // The extended header advertises which metadata chunks follow
+    let vp8x = chunks.iter().find(|chunk| &chunk.fourcc == b"VP8X").map(|chunk| {
+        let mut payload = chunk.payload.to_vec();
+        if let Some(flags) = payload.first_mut() {
+            *flags &= !(WEBP_FLAG_ICC | WEBP_FLAG_EXIF | WEBP_FLAG_XMP);
+        }
+        payload
+    });
+    if let Some(payload) = &vp8x {
+        for chunk in chunks.iter_mut().filter(|chunk| &chunk.fourcc == b"VP8X") {
+            chunk.payload = payload;
+        }
+    }
+
+    Ok(write_webp(&chunks))
+}
+
+/// Reads the canvas size and alpha usage from a simple-format WebP bitstream chunk.
+fn webp_canvas(chunk: &WebpChunk<'_>) -> Option<(u32, u32, bool)> {
+    let p = chunk.payload;
+    match &chunk.fourcc {
+        b"VP8L" if p.len() >= 5 && p[0] == 0x2F => {
+            let bits = u32::from_le_bytes([p[1], p[2], p[3], p[4]]);
+            let width = (bits & 0x3FFF) + 1;
+            let height = ((bits >> 14) & 0x3FFF) + 1;
+            let alpha = (bits >> 28) & 1 == 1;
+            Some((width, height, alpha))
+        }
+        b"VP8 " if p.len() >= 10 && p[3..6] == [0x9D, 0x01, 0x2A] => {
+            let width = u16::from_le_bytes([p[6], p[7]]) as u32 & 0x3FFF;
+            let height = u16::from_le_bytes([p[8], p[9]]) as u32 & 0x3FFF;
+            Some((width, height, false))
+        }
+        _ => None,
+    }
+}
+
+/// Adds a metadata chunk, converting a simple WebP into the extended (VP8X) layout if needed.
+fn insert_webp_chunk(data: &[u8], fourcc: &[u8; 4], payload: &[u8], flag: u8) -> Result<Vec<u8>> {
+    let mut chunks = webp_chunks(data)?;
+    chunks.retain(|chunk| &chunk.fourcc != fourcc);
+
+    let mut vp8x = match chunks.iter().find(|chunk| &chunk.fourcc == b"VP8X") {
+        Some(chunk) if chunk.payload.len() >= 10 => chunk.payload.to_vec(),
+        Some(_) => return Err(invalid("WebP")),
+        None => {
+            let (width, height, alpha) = chunks
+                .iter()
+                .find_map(webp_canvas)
+                .ok_or_else(|| invalid("WebP"))?;
+            let mut header = vec![0u8; 10];
+            if alpha {
+                header[0] |= WEBP_FLAG_ALPHA;
+            }
+            header[4..7].copy_from_slice(&(width - 1).to_le_bytes()[..3]);
+            header[7..10].copy_from_slice(&(height - 1).to_le_bytes()[..3]);
+            header
+        }
+    };
+    vp8x[0] |= flag;
+
+    chunks.retain(|chunk| &chunk.fourcc != b"VP8X");
+
+    // Canonical order: VP8X, ICCP, image data, EXIF, XMP
+    let position = match fourcc {
+        b"ICCP" => 0,
+        b"EXIF" => chunks
+            .iter()
+            .position(|chunk| &chunk.fourcc == b"XMP ")
+            .unwrap_or(chunks.len()),
+        _ => chunks.len(),
+    };
+    chunks.insert(position, WebpChunk { fourcc: *fourcc, payload });
+    chunks.insert(0, WebpChunk { fourcc: *b"VP8X", payload: &vp8x });
+
+    Ok(write_webp(&chunks))
+}
@@ -1,9 +1,23 @@
 // pixie/src/processors/loader.rs
 use crate::core::{ImageToolError, Result};
-use image::{DynamicImage, ImageFormat, ImageReader, GenericImageView};
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader, GenericImageView};
 use crate::utils::image_format_to_string;
+use std::io::{BufRead, Cursor, Seek};
 use std::path::Path;
 
+/// A decoded image together with the metadata stored in its container.
+#[derive(Debug, Clone)]
+pub struct LoadedImage {
+    pub image: DynamicImage,
+    pub format: Option<ImageFormat>,
+    /// Raw EXIF block (TIFF structure, without the `Exif\0\0` prefix).
+    pub exif: Option<Vec<u8>>,
+    /// Raw XMP packet.
+    pub xmp: Option<Vec<u8>>,
+    /// Embedded ICC color profile.
+    pub icc_profile: Option<Vec<u8>>,
+}
+
 #[derive(Clone)]
 pub struct Loader {
     max_dimensions: Option<(u32, u32)>,
@@ -22,55 +36,54 @@ impl Loader {
     }
 
     pub fn load(&self, path: &Path) -> Result<DynamicImage> {
+        Ok(self.load_with_metadata(path)?.image)
+    }
+
+    pub fn load_with_metadata(&self, path: &Path) -> Result<LoadedImage> {
         log::debug!("Loading image from: {}", path.display());
 
         self.validate_path(path)?;
 
-        let image = ImageReader::open(path)?
-            .with_guessed_format()?
-            .decode()
-            .map_err(|e| {
-                ImageToolError::ProcessingError(format!("Failed to decode image: {}", e))
-            })?;
+        let reader = ImageReader::open(path)?.with_guessed_format()?;
+        let loaded = self.decode(reader, "Failed to decode image")?;
 
-        // Validate dimensions
-        if let Some((max_w, max_h)) = self.max_dimensions {
-            let (width, height) = image.dimensions();
-            if width > max_w || height > max_h {
-                return Err(ImageToolError::MemoryLimitExceeded(
-                    format!("Image dimensions {}x{} exceed maximum {}x{}", 
-                        width, height, max_w, max_h)
-                ));
-            }
-        }
-
-        let (width, height) = image.dimensions();
-        let format = image.color();
+        let (width, height) = loaded.image.dimensions();
+        let format = loaded.image.color();
 
         log::info!(
             "Loaded image: {}x{} pixels, format: {:?}",
             width, height, format
         );
 
-        Ok(image)
+        Ok(loaded)
     }
 
     pub fn load_from_bytes(&self, data: &[u8]) -> Result<DynamicImage> {
-        let image = image::load_from_memory(data)
-            .map_err(|e| {
-                ImageToolError::ProcessingError(format!("Failed to decode image from bytes: {}", e))
-            })?;
+        Ok(self.load_from_bytes_with_metadata(data)?.image)
+    }
+
+    pub fn load_from_bytes_with_metadata(&self, data: &[u8]) -> Result<LoadedImage> {
+        if data.is_empty() {
+            return Err(ImageToolError::InvalidParameter("Image data is empty".to_string()));
+        }
 
-        Ok(image)
+        let reader = ImageReader::new(Cursor::new(data)).with_guessed_format()?;
+        self.decode(reader, "Failed to decode image from bytes")
+    }
+
+    /// Detects the format of encoded image data from its magic bytes.
+    pub fn detect_format_from_bytes(&self, data: &[u8]) -> Result<ImageFormat> {
+        image::guess_format(data)
+            .map_err(|_| ImageToolError::UnsupportedFormat("Unrecognized image data".to_string()))
     }
 
     pub fn get_dimensions_and_format(&self, path: &Path) -> Result<(u32, u32, String)> {
         let file = std::fs::File::open(path)?;
-        let reader = image::io::Reader::new(std::io::BufReader::new(file))
+        let reader = image::ImageReader::new(std::io::BufReader::new(file))
             .with_guessed_format()?;
         
         let format = reader.format()
-            .map(|f| image_format_to_string(f))
+            .map(image_format_to_string)
             .unwrap_or_else(|| "Unknown".to_string());
         
         let dimensions = reader.into_dimensions()?;
@@ -85,6 +98,54 @@ impl Loader {
         Ok(format)
     }
 
+    fn decode<R: BufRead + Seek>(&self, reader: ImageReader<R>, error_context: &str) -> Result<LoadedImage> {
+        let format = reader.format();
+        let decode_error = |e: image::ImageError| {
+            ImageToolError::ProcessingError(format!("{}: {}", error_context, e))
+        };
+
+        let mut decoder = reader.into_decoder().map_err(decode_error)?;
+
+        // Metadata is best-effort: a damaged EXIF block should not stop the pixels from loading
+        let exif = decoder.exif_metadata().unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable EXIF metadata: {}", e);
+            None
+        });
+        let xmp = decoder.xmp_metadata().unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable XMP metadata: {}", e);
+            None
+        });
+        let icc_profile = decoder.icc_profile().unwrap_or_else(|e| {
+            log::warn!("Ignoring unreadable ICC profile: {}", e);
+            None
+        });
+
+        let image = DynamicImage::from_decoder(decoder).map_err(decode_error)?;
+        self.validate_dimensions(&image)?;
+
+        Ok(LoadedImage {
+            image,
+            format,
+            exif,
+            xmp,
+            icc_profile,
+        })
+    }
+
+    fn validate_dimensions(&self, image: &DynamicImage) -> Result<()> {
+        if let Some((max_w, max_h)) = self.max_dimensions {
+            let (width, height) = image.dimensions();
+            if width > max_w || height > max_h {
+                return Err(ImageToolError::MemoryLimitExceeded(
+                    format!("Image dimensions {}x{} exceed maximum {}x{}", 
+                        width, height, max_w, max_h)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     fn validate_path(&self, path: &Path) -> Result<()> {
         if !path.exists() {
             return Err(ImageToolError::InvalidParameter(
@@ -1,11 +1,43 @@
 // pixie/src/processors/metadata.rs
-use crate::core::{ImageToolError, Result};
-use exif::{Exif, In, Tag, Reader};
-use image::DynamicImage;
+use crate::core::{ImageToolError, MetadataRules, Operation, PipelineContext, ProcessConfig, Result};
+use crate::processors::container;
+use exif::{Context, Exif, Field, In, Tag, Reader, Value};
+use image::{DynamicImage, ImageFormat};
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, Cursor};
 use std::path::Path;
 
+/// Tags that identify a specific camera body, lens or owner.
+const SERIAL_TAGS: [Tag; 5] = [
+    Tag::BodySerialNumber,
+    Tag::LensSerialNumber,
+    Tag::ImageUniqueID,
+    Tag::CameraOwnerName,
+    // Vendor blobs routinely embed serial numbers
+    Tag::MakerNote,
+];
+
+const CAMERA_TAGS: [Tag; 6] = [
+    Tag::Make,
+    Tag::Model,
+    Tag::LensMake,
+    Tag::LensModel,
+    Tag::LensSpecification,
+    Tag::Software,
+];
+
+const DATETIME_TAGS: [Tag; 9] = [
+    Tag::DateTime,
+    Tag::DateTimeOriginal,
+    Tag::DateTimeDigitized,
+    Tag::OffsetTime,
+    Tag::OffsetTimeOriginal,
+    Tag::OffsetTimeDigitized,
+    Tag::SubSecTime,
+    Tag::SubSecTimeOriginal,
+    Tag::SubSecTimeDigitized,
+];
+
 pub struct MetadataProcessor;
 
 impl MetadataProcessor {
@@ -13,17 +45,132 @@ impl MetadataProcessor {
         Self
     }
 
-    pub fn strip_metadata(
-        &self,
-        image: &mut DynamicImage,
-        _path: &Path,
-    ) -> Result<()> {
-        log::debug!("Metadata stripping requested");
-        
-        // For JPEG images, we need to re-encode to strip metadata
-        // The image crate automatically strips most metadata when re-encoding
-        // This is handled in the compressor
-        Ok(())
+    /// Removes EXIF, XMP, IPTC, ICC and text blocks from encoded JPEG, PNG or WebP data.
+    pub fn strip_metadata(&self, data: &[u8], format: ImageFormat) -> Result<Vec<u8>> {
+        log::debug!("Stripping metadata from {:?} output", format);
+        container::strip(data, format)
+    }
+
+    /// Embeds a raw EXIF block into encoded JPEG, PNG or WebP data.
+    pub fn embed_exif(&self, data: &[u8], format: ImageFormat, exif: &[u8]) -> Result<Vec<u8>> {
+        container::embed_exif(data, format, exif)
+    }
+
+    pub fn read_metadata_from_bytes(&self, exif: &[u8]) -> Result<Exif> {
+        Reader::new()
+            .read_raw(exif.to_vec())
+            .map_err(|e| ImageToolError::ProcessingError(format!("EXIF read error: {}", e)))
+    }
+
+    /// Embeds an ICC color profile into encoded JPEG, PNG or WebP data.
+    pub fn embed_icc_profile(&self, data: &[u8], format: ImageFormat, icc_profile: &[u8]) -> Result<Vec<u8>> {
+        container::embed_icc_profile(data, format, icc_profile)
+    }
+
+    /// Embeds a raw XMP packet into encoded JPEG, PNG or WebP data.
+    pub fn embed_xmp(&self, data: &[u8], format: ImageFormat, xmp: &[u8]) -> Result<Vec<u8>> {
+        container::embed_xmp(data, format, xmp)
+    }
+
+    /// Rebuilds a raw EXIF block from the primary-image fields accepted by `keep`.
+    ///
+    /// The thumbnail IFD is always dropped since it no longer matches processed pixels.
+    /// Returns `None` when no field survives.
+    pub fn filter_exif<F>(&self, exif: &[u8], keep: F) -> Result<Option<Vec<u8>>>
+    where
+        F: Fn(&Field) -> bool,
+    {
+        self.rewrite_exif(exif, |field| keep(field).then(|| field.clone()))
+    }
+
+    /// Re-serializes a raw EXIF block for a `width` x `height` image, updating its size tags.
+    ///
+    /// The thumbnail IFD is dropped like in [`Self::filter_exif`], since it still shows the
+    /// unprocessed pixels. Returns `None` when no field survives.
+    pub fn update_dimensions(&self, exif: &[u8], width: u32, height: u32) -> Result<Option<Vec<u8>>> {
+        self.rewrite_exif(exif, |field| {
+            let mut field = field.clone();
+            if let Some(value) = Self::dimension_value(field.tag, width, height) {
+                field.value = Value::Long(vec![value]);
+            }
+            Some(field)
+        })
+    }
+
+    fn dimension_value(tag: Tag, width: u32, height: u32) -> Option<u32> {
+        match tag {
+            Tag::PixelXDimension | Tag::ImageWidth => Some(width),
+            Tag::PixelYDimension | Tag::ImageLength => Some(height),
+            _ => None,
+        }
+    }
+
+    /// Re-serializes the primary-image fields of a raw EXIF block through `map`.
+    fn rewrite_exif<F>(&self, exif: &[u8], map: F) -> Result<Option<Vec<u8>>>
+    where
+        F: Fn(&Field) -> Option<Field>,
+    {
+        let parsed = self.read_metadata_from_bytes(exif)?;
+        let fields: Vec<Field> = parsed
+            .fields()
+            .filter(|field| field.ifd_num == In::PRIMARY)
+            .filter(|field| !matches!(field.value, Value::Unknown(..)))
+            .filter(|field| !Self::is_structural(field.tag))
+            .filter_map(&map)
+            .collect();
+
+        if fields.is_empty() {
+            return Ok(None);
+        }
+
+        let mut writer = exif::experimental::Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+
+        let mut buffer = Cursor::new(Vec::new());
+        writer
+            .write(&mut buffer, parsed.little_endian())
+            .map_err(|e| ImageToolError::ProcessingError(format!("EXIF write error: {}", e)))?;
+
+        Ok(Some(buffer.into_inner()))
+    }
+
+    /// Applies `rules` to a raw EXIF block; see [`MetadataRules`] for the semantics.
+    pub fn apply_rules(&self, exif: &[u8], rules: &MetadataRules, strip: bool) -> Result<Option<Vec<u8>>> {
+        self.filter_exif(exif, |field| {
+            let kept = rules.keep.iter().any(|name| Self::rule_matches(name, field));
+            let dropped = rules.drop.iter().any(|name| Self::rule_matches(name, field));
+            kept || (!strip && !dropped)
+        })
+    }
+
+    /// Matches a tag name or group name against an EXIF field.
+    pub fn rule_matches(name: &str, field: &Field) -> bool {
+        let name = name.trim();
+        match name.to_ascii_lowercase().as_str() {
+            "gps" => field.tag.context() == Context::Gps,
+            "serial" => SERIAL_TAGS.contains(&field.tag),
+            "camera" => CAMERA_TAGS.contains(&field.tag),
+            "datetime" => DATETIME_TAGS.contains(&field.tag),
+            _ => field.tag.to_string().eq_ignore_ascii_case(name),
+        }
+    }
+
+    /// Tags describing the TIFF layout itself; the writer regenerates them.
+    fn is_structural(tag: Tag) -> bool {
+        matches!(
+            tag,
+            Tag::ExifIFDPointer
+                | Tag::GPSInfoIFDPointer
+                | Tag::InteropIFDPointer
+                | Tag::StripOffsets
+                | Tag::StripByteCounts
+                | Tag::TileOffsets
+                | Tag::TileByteCounts
+                | Tag::JPEGInterchangeFormat
+                | Tag::JPEGInterchangeFormatLength
+        )
     }
 
     pub fn read_metadata(&self, path: &Path) -> Result<Option<Exif>> {
@@ -223,33 +370,20 @@ impl MetadataProcessor {
     }
 
     pub fn get_exposure_info(&self, exif: &Exif) -> Option<(String, String, String, String)> {
-        let exposure_time = exif.get_field(Tag::ExposureTime, In::PRIMARY)
-        .and_then(|f| {
-            let display = f.value.display_as(f.tag);
-            Some(format!("{}", display))
-        });
-        let aperture = exif.get_field(Tag::FNumber, In::PRIMARY)
-            .and_then(|f| {
-                let display = f.value.display_as(f.tag);
-                Some(format!("{}", display))
-            });
-            
-            let iso = exif.get_field(Tag::PhotographicSensitivity, In::PRIMARY)
-                .and_then(|f| {
-                let display = f.value.display_as(f.tag);
-                Some(format!("{}", display))
-            });
-            
-            let focal_length = exif.get_field(Tag::FocalLength, In::PRIMARY)
-                .and_then(|f| {
-                let display = f.value.display_as(f.tag);
-                Some(format!("{}", display))
-            });
-            
-            match (exposure_time, aperture, iso, focal_length) {
-                (Some(et), Some(ap), Some(i), Some(fl)) => Some((et, ap, i, fl)),
-        _ => None,          
-         }
+        let display_field = |tag: Tag| {
+            exif.get_field(tag, In::PRIMARY)
+                .map(|f| format!("{}", f.value.display_as(f.tag)))
+        };
+
+        let exposure_time = display_field(Tag::ExposureTime);
+        let aperture = display_field(Tag::FNumber);
+        let iso = display_field(Tag::PhotographicSensitivity);
+        let focal_length = display_field(Tag::FocalLength);
+
+        match (exposure_time, aperture, iso, focal_length) {
+            (Some(et), Some(ap), Some(i), Some(fl)) => Some((et, ap, i, fl)),
+            _ => None,
+        }
     }
 }
 
@@ -257,4 +391,66 @@ impl Default for MetadataProcessor {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+/// Applies strip/keep/drop rules to the EXIF carried by the pipeline.
+///
+/// The operation only decides what survives; the encoding step scrubs the
+/// output container and embeds the remaining EXIF.
+pub struct MetadataOperation {
+    processor: MetadataProcessor,
+    strip: bool,
+    rules: MetadataRules,
+}
+
+impl MetadataOperation {
+    /// Removes all metadata except fields matched by `rules.keep`.
+    pub fn strip(rules: MetadataRules) -> Self {
+        Self {
+            processor: MetadataProcessor::new(),
+            strip: true,
+            rules,
+        }
+    }
+
+    /// Keeps the source EXIF minus fields matched by `rules.drop`.
+    pub fn filter(rules: MetadataRules) -> Self {
+        Self {
+            processor: MetadataProcessor::new(),
+            strip: false,
+            rules,
+        }
+    }
+
+    /// Returns `None` when `config` neither strips nor filters metadata.
+    pub fn from_config(config: &ProcessConfig) -> Option<Self> {
+        if config.strip_metadata {
+            Some(Self::strip(config.metadata_rules.clone()))
+        } else if !config.metadata_rules.is_empty() {
+            Some(Self::filter(config.metadata_rules.clone()))
+        } else {
+            None
+        }
+    }
+}
+
+impl Operation for MetadataOperation {
+    fn name(&self) -> &str {
+        "metadata"
+    }
+
+    fn apply(&self, image: DynamicImage, context: &mut PipelineContext) -> Result<DynamicImage> {
+        // Keep/drop rules address EXIF tags; XMP and ICC only survive when not stripping
+        if self.strip {
+            context.strip_metadata = true;
+            context.xmp = None;
+            context.icc_profile = None;
+        }
+
+        if let Some(exif) = context.exif.take() {
+            context.exif = self.processor.apply_rules(&exif, &self.rules, self.strip)?;
+        }
+
+        Ok(image)
+    }
+}
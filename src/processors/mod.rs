@@ -1,16 +1,37 @@
 // pixie/src/processors/mod.rs
+mod adjust;
+mod color;
 mod compressor;
+mod contact;
+mod convolution;
+mod container;
 mod loader;
 mod metadata;
+mod orientation;
+mod redact;
 mod resizer;
+mod responsive;
+mod sharpen;
+mod ssim;
+mod text;
+mod transform;
+mod watermark;
 mod batch;
 
-pub use compressor::Compressor;
-pub use loader::Loader;
-pub use metadata::MetadataProcessor;
-pub use resizer::{Resizer, ResizeMode};
-pub use batch::BatchProcessor;
-
-pub mod prelude {
-    pub use super::{Compressor, Loader, MetadataProcessor, Resizer, BatchProcessor};
-}
\ No newline at end of file
+pub use adjust::{Adjuster, AdjustOperation};
+pub use color::{ColorConverter, ConvertToSrgbOperation};
+pub use compressor::{Compressor, CompressOperation};
+pub use contact::{ContactSheetMaker, ContactSheetPages};
+pub use convolution::{ConvolutionResizer, ResampleFilter};
+pub use loader::{Loader, LoadedImage};
+pub use metadata::{MetadataProcessor, MetadataOperation};
+pub use orientation::AutoOrientOperation;
+pub use redact::{Redactor, RedactOperation};
+pub use resizer::{Resizer, ResizeMode, ResizeOperation};
+pub use responsive::{ResponsiveGenerator, ResponsiveSet, Variant};
+pub use sharpen::{Sharpener, SharpenOperation};
+pub use ssim::ssim;
+pub use text::{TextRenderer, TextOverlayOperation};
+pub use transform::{Transformer, TransformOperation};
+pub use watermark::{Watermarker, WatermarkOperation};
+pub use batch::BatchProcessor;
\ No newline at end of file
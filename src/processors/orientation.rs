@@ -0,0 +1,49 @@
+// pixie/src/processors/orientation.rs
+use crate::core::{Operation, PipelineContext, Result};
+use image::metadata::Orientation;
+use image::DynamicImage;
+
+/// Applies the EXIF Orientation tag to the pixels so the image displays upright
+/// without relying on the viewer.
+///
+/// The tag in `PipelineContext::exif` is reset to 1 afterwards, so a preserved
+/// EXIF block does not make viewers rotate the image a second time.
+pub struct AutoOrientOperation;
+
+impl AutoOrientOperation {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Reads the orientation stored in a raw EXIF block, if any.
+    pub fn orientation(exif: &[u8]) -> Option<Orientation> {
+        Orientation::from_exif_chunk(exif)
+    }
+}
+
+impl Default for AutoOrientOperation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Operation for AutoOrientOperation {
+    fn name(&self) -> &str {
+        "auto-orient"
+    }
+
+    fn apply(&self, mut image: DynamicImage, context: &mut PipelineContext) -> Result<DynamicImage> {
+        let Some(exif) = context.exif.as_mut() else {
+            return Ok(image);
+        };
+
+        if let Some(orientation) = Orientation::remove_from_exif_chunk(exif) {
+            if orientation != Orientation::NoTransforms {
+                log::debug!("Applying EXIF orientation {:?}", orientation);
+                image.apply_orientation(orientation);
+            }
+        }
+
+        Ok(image)
+    }
+}
@@ -0,0 +1,244 @@
+// pixie/src/processors/redact.rs
+use crate::core::{
+    ImageToolError, Operation, PipelineContext, ProcessConfig, RedactStyle, Redaction, Region, Result,
+};
+use image::{imageops, DynamicImage, GenericImageView, Rgba, Rgba32FImage};
+use imageproc::filter::gaussian_blur_f32;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use super::{MetadataProcessor, Resizer};
+
+/// Regions listed for one image, e.g.
+/// `{"regions": [{"x": 120, "y": 40, "width": 80, "height": 30}]}`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Sidecar {
+    regions: Vec<SidecarRegion>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SidecarRegion {
+    x: f64,
+    y: f64,
+    width: f64,
+    height: f64,
+    /// Values are fractions of the image size instead of pixels.
+    #[serde(default)]
+    relative: bool,
+}
+
+impl SidecarRegion {
+    fn to_region(&self) -> std::result::Result<Region, String> {
+        let values = [self.x, self.y, self.width, self.height];
+        if self.relative {
+            if !values.iter().all(|v| (0.0..=1.0).contains(v)) {
+                return Err("relative region values must be between 0 and 1".to_string());
+            }
+            let [x, y, width, height] = values.map(|v| v as f32);
+            Ok(Region::Relative { x, y, width, height })
+        } else {
+            if !values.iter().all(|v| v.fract() == 0.0 && (0.0..=u32::MAX as f64).contains(v)) {
+                return Err("pixel region values must be whole non-negative numbers".to_string());
+            }
+            let [x, y, width, height] = values.map(|v| v as u32);
+            Ok(Region::Pixels { x, y, width, height })
+        }
+    }
+}
+
+/// Hides regions of images by blurring, pixelating or filling them.
+pub struct Redactor {
+    style: RedactStyle,
+}
+
+impl Redactor {
+    pub fn new(style: RedactStyle) -> Self {
+        Self { style }
+    }
+
+    /// Hides `regions` of `image`, keeping its color type; pixels outside them are untouched.
+    ///
+    /// Regions are clipped to the image; one entirely outside it is an error, as it was
+    /// likely meant for another size or orientation and would leave its area visible.
+    pub fn apply(&self, image: &DynamicImage, regions: &[Region]) -> Result<DynamicImage> {
+        let (width, height) = image.dimensions();
+        let rects = regions
+            .iter()
+            .map(|region| {
+                region.to_pixels(width, height).ok_or_else(|| {
+                    ImageToolError::InvalidParameter(format!(
+                        "Redaction region {:?} lies outside the {}x{} image",
+                        region, width, height
+                    ))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        if rects.is_empty() {
+            return Ok(image.clone());
+        }
+
+        let mut canvas = image.to_rgba32f();
+        for &(x, y, rect_width, rect_height) in &rects {
+            let shorter = rect_width.min(rect_height);
+            match self.style {
+                RedactStyle::Blur { sigma } => {
+                    let sigma = sigma.unwrap_or((shorter as f32 / 6.0).max(2.0));
+                    Self::blur(&mut canvas, (x, y, rect_width, rect_height), sigma);
+                }
+                RedactStyle::Pixelate { block } => {
+                    // Clamped here too, as a Redactor may be built without a validated config
+                    let block = block.unwrap_or((shorter / 6).max(4)).max(1);
+                    Self::pixelate(&mut canvas, (x, y, rect_width, rect_height), block);
+                }
+                RedactStyle::Fill(color) => {
+                    let fill = Rgba(color.map(|c| c as f32 / 255.0));
+                    for py in y..y + rect_height {
+                        for px in x..x + rect_width {
+                            canvas.put_pixel(px, py, fill);
+                        }
+                    }
+                }
+            }
+        }
+
+        log::debug!("Redacted {} region(s) of a {}x{} image", rects.len(), width, height);
+        Ok(Resizer::restore_color_type(DynamicImage::ImageRgba32F(canvas), image.color()))
+    }
+
+    /// Blurs the rectangle, sampling the pixels around it so its edges blend in.
+    fn blur(canvas: &mut Rgba32FImage, (x, y, width, height): (u32, u32, u32, u32), sigma: f32) {
+        let pad = (3.0 * sigma).ceil() as u32;
+        let (x0, y0) = (x.saturating_sub(pad), y.saturating_sub(pad));
+        let x1 = (x + width).saturating_add(pad).min(canvas.width());
+        let y1 = (y + height).saturating_add(pad).min(canvas.height());
+
+        // Premultiplied, so transparent pixels do not bleed their hidden color
+        let mut area = imageops::crop_imm(canvas, x0, y0, x1 - x0, y1 - y0).to_image();
+        Resizer::premultiply(&mut area);
+        let mut blurred = gaussian_blur_f32(&area, sigma);
+        Resizer::unpremultiply(&mut blurred);
+
+        let inner = imageops::crop_imm(&blurred, x - x0, y - y0, width, height).to_image();
+        imageops::replace(canvas, &inner, x as i64, y as i64);
+    }
+
+    /// Replaces `block`-sized squares of the rectangle, from its corner, with their average.
+    fn pixelate(canvas: &mut Rgba32FImage, (x, y, width, height): (u32, u32, u32, u32), block: u32) {
+        let mut area = imageops::crop_imm(canvas, x, y, width, height).to_image();
+        Resizer::premultiply(&mut area);
+
+        for block_y in (0..height).step_by(block as usize) {
+            for block_x in (0..width).step_by(block as usize) {
+                let (block_width, block_height) = (block.min(width - block_x), block.min(height - block_y));
+                let mut sum = [0f32; 4];
+                for py in block_y..block_y + block_height {
+                    for px in block_x..block_x + block_width {
+                        for (total, value) in sum.iter_mut().zip(area.get_pixel(px, py).0) {
+                            *total += value;
+                        }
+                    }
+                }
+
+                let count = (block_width * block_height) as f32;
+                let average = Rgba(sum.map(|total| total / count));
+                for py in block_y..block_y + block_height {
+                    for px in block_x..block_x + block_width {
+                        area.put_pixel(px, py, average);
+                    }
+                }
+            }
+        }
+
+        Resizer::unpremultiply(&mut area);
+        imageops::replace(canvas, &area, x as i64, y as i64);
+    }
+
+    /// Sidecar listing the regions of `image_path`: `photo.jpg` reads `photo.jpg.json`.
+    pub fn sidecar_path(image_path: &Path) -> PathBuf {
+        let mut path = image_path.as_os_str().to_owned();
+        path.push(".json");
+        PathBuf::from(path)
+    }
+
+    /// Parses a sidecar such as
+    /// `{"regions": [{"x": 120, "y": 40, "width": 80, "height": 30}]}`; regions with
+    /// `"relative": true` are in fractions of the image size.
+    pub fn parse_sidecar(json: &str) -> Result<Vec<Region>> {
+        Self::parse(json)
+            .map_err(|e| ImageToolError::InvalidParameter(format!("Invalid redaction sidecar: {}", e)))
+    }
+
+    /// Reads the sidecar of `image_path`; an image without one has no extra regions.
+    pub fn read_sidecar(image_path: &Path) -> Result<Vec<Region>> {
+        let path = Self::sidecar_path(image_path);
+        let json = match std::fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+
+        log::debug!("Reading redaction regions from {}", path.display());
+        Self::parse(&json).map_err(|e| {
+            ImageToolError::InvalidParameter(format!("Invalid redaction sidecar {}: {}", path.display(), e))
+        })
+    }
+
+    fn parse(json: &str) -> std::result::Result<Vec<Region>, String> {
+        let sidecar: Sidecar = serde_json::from_str(json).map_err(|e| e.to_string())?;
+        sidecar.regions.iter().map(SidecarRegion::to_region).collect()
+    }
+}
+
+/// Hides the configured regions, plus those of each file's sidecar.
+///
+/// The EXIF thumbnail still shows the original pixels, so it is dropped from any image
+/// that had something redacted.
+pub struct RedactOperation {
+    redaction: Redaction,
+    metadata_processor: MetadataProcessor,
+}
+
+impl RedactOperation {
+    pub fn new(redaction: Redaction) -> Self {
+        Self {
+            redaction,
+            metadata_processor: MetadataProcessor::new(),
+        }
+    }
+
+    pub fn from_config(config: &ProcessConfig) -> Option<Self> {
+        config.redaction.clone().map(Self::new)
+    }
+}
+
+impl Operation for RedactOperation {
+    fn name(&self) -> &str {
+        "redact"
+    }
+
+    fn apply(&self, image: DynamicImage, context: &mut PipelineContext) -> Result<DynamicImage> {
+        let mut regions = self.redaction.regions.clone();
+        if self.redaction.sidecars {
+            match &context.source_path {
+                Some(path) => regions.extend(Redactor::read_sidecar(path)?),
+                None => log::warn!("Input is not a file, so there is no redaction sidecar to read"),
+            }
+        }
+
+        if regions.is_empty() {
+            return Ok(image);
+        }
+
+        if let Some(exif) = context.exif.take() {
+            context.exif = self.metadata_processor.filter_exif(&exif, |_| true)?;
+        }
+
+        Redactor::new(self.redaction.style).apply(&image, &regions).map_err(|e| match (e, &context.source_path) {
+            (ImageToolError::InvalidParameter(message), Some(path)) => {
+                ImageToolError::InvalidParameter(format!("{}: {}", path.display(), message))
+            }
+            (e, _) => e,
+        })
+    }
+}
@@ -1,6 +1,12 @@
 // pixie/src/processors/resizer.rs
-use crate::core::{ImageToolError, ResizeAlgorithm, Result};
-use image::{DynamicImage, imageops::FilterType, GenericImageView};
+use crate::core::{
+    Background, Fit, Gravity, Operation, PipelineContext, ProcessConfig, ResizeAlgorithm,
+    ResizePolicy, Result,
+};
+use super::convolution::{ConvolutionResizer, ResampleFilter};
+use image::{
+    ColorType, DynamicImage, imageops::FilterType, GenericImageView, ImageBuffer, Pixel, Rgba32FImage,
+};
 
 #[derive(Debug, Clone, Copy)]
 pub enum ResizeMode {
@@ -8,19 +14,108 @@ pub enum ResizeMode {
     Scale(f32),
     Width(u32),
     Height(u32),
+    /// Scale so the longer side has this length.
+    LongestEdge(u32),
+    /// Scale so the shorter side has this length.
+    ShortestEdge(u32),
+    /// Shrink to at most this many pixels; smaller images are left as they are.
+    MaxPixels(u64),
+    /// Scale to fill `width` x `height` and crop the overflow around the gravity.
+    Cover(u32, u32, Gravity),
+    /// Fit inside `width` x `height` and place the result on a canvas of exactly that size.
+    Pad(u32, u32, Gravity, Background),
 }
 
 pub struct Resizer {
     algorithm: ResizeAlgorithm,
     keep_aspect: bool,
+    policy: ResizePolicy,
+    linear_light: bool,
 }
 
 impl Resizer {
     pub fn new(algorithm: ResizeAlgorithm, keep_aspect: bool) -> Self {
-        Self { algorithm, keep_aspect }
+        Self {
+            algorithm,
+            keep_aspect,
+            policy: ResizePolicy::Always,
+            linear_light: Self::default_linear_light(algorithm),
+        }
+    }
+
+    /// The resampling settings of `config`: algorithm, aspect, policy and linear light.
+    pub fn from_config(config: &ProcessConfig) -> Self {
+        let linear_light = config
+            .linear_light
+            .unwrap_or_else(|| Self::default_linear_light(config.algorithm));
+        Self::new(config.algorithm, config.keep_aspect)
+            .with_policy(config.resize_policy)
+            .with_linear_light(linear_light)
+    }
+
+    /// Whether `algorithm` filters in linear light unless told otherwise.
+    pub fn default_linear_light(algorithm: ResizeAlgorithm) -> bool {
+        matches!(algorithm, ResizeAlgorithm::Lanczos3 | ResizeAlgorithm::Bicubic)
+    }
+
+    /// Filter in linear light instead of on sRGB-encoded values.
+    pub fn with_linear_light(mut self, linear_light: bool) -> Self {
+        self.linear_light = linear_light;
+        self
+    }
+
+    pub fn with_policy(mut self, policy: ResizePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Explains why the policy forbids resizing `image` with `mode`, or `None` if allowed.
+    ///
+    /// Cover and pad always produce their canvas; the policy only limits how far the
+    /// image is scaled within it.
+    pub fn skip_reason(&self, image: &DynamicImage, mode: ResizeMode) -> Option<String> {
+        if matches!(mode, ResizeMode::Cover(..) | ResizeMode::Pad(..)) {
+            return None;
+        }
+
+        let (orig_width, orig_height) = image.dimensions();
+        let (width, height) = self.calculate_dimensions(image, mode);
+        let (scale_x, scale_y) = (width as f32 / orig_width as f32, height as f32 / orig_height as f32);
+
+        let describe = |verb: &str, policy: &str| {
+            let (width, height) = self.calculate_dimensions(image, mode);
+            format!(
+                "{}x{} would be {} to {}x{} ({})",
+                orig_width, orig_height, verb, width, height, policy
+            )
+        };
+
+        match self.policy {
+            ResizePolicy::Always => None,
+            ResizePolicy::ShrinkOnly if scale_x > 1.0 || scale_y > 1.0 => {
+                Some(describe("enlarged", "shrink-only"))
+            }
+            ResizePolicy::EnlargeOnly if scale_x < 1.0 || scale_y < 1.0 => {
+                Some(describe("shrunk", "enlarge-only"))
+            }
+            _ => None,
+        }
     }
 
     pub fn resize(&self, image: &DynamicImage, mode: ResizeMode) -> DynamicImage {
+        if let Some(reason) = self.skip_reason(image, mode) {
+            log::debug!("Skipping resize: {}", reason);
+            return image.clone();
+        }
+
+        if let ResizeMode::Cover(width, height, gravity) = mode {
+            return self.resize_cover(image, width, height, gravity);
+        }
+
+        if let ResizeMode::Pad(width, height, gravity, background) = mode {
+            return self.resize_pad(image, width, height, gravity, background);
+        }
+
         let (width, height) = self.calculate_dimensions(image, mode);
         
         if width == image.width() && height == image.height() {
@@ -36,22 +131,213 @@ impl Resizer {
             height
         );
 
-        let filter = self.get_filter_type();
+        // The dimensions already honour keep_aspect
+        self.resize_exact(image, width, height)
+    }
 
-        if self.keep_aspect {
-            image.resize(width, height, filter)
+    pub fn resize_exact(&self, image: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+        if width == image.width() && height == image.height() {
+            return image.clone();
+        }
+
+        // Nearest-neighbour never mixes pixels, so neither gamma nor alpha matter
+        let Some(filter) = self.resample_filter() else {
+            return image.resize_exact(width, height, FilterType::Nearest);
+        };
+        let engine = ConvolutionResizer::new(filter);
+
+        // Float images are linear already
+        let is_float = matches!(image.color(), ColorType::Rgb32F | ColorType::Rgba32F);
+        let linear = self.linear_light && !is_float;
+
+        let resized = if image.color().has_alpha() {
+            // Premultiply so the color of transparent pixels cannot bleed into visible ones
+            let mut buffer = image.to_rgba32f();
+            if linear {
+                Self::map_color_channels(&mut buffer, srgb_to_linear);
+            }
+            Self::premultiply(&mut buffer);
+            let mut buffer = engine.resize(&buffer, width, height);
+            Self::unpremultiply(&mut buffer);
+            if linear {
+                Self::map_color_channels(&mut buffer, linear_to_srgb);
+            }
+            DynamicImage::ImageRgba32F(buffer)
         } else {
-            image.resize_exact(width, height, filter)
+            let mut buffer = image.to_rgb32f();
+            if linear {
+                Self::map_color_channels(&mut buffer, srgb_to_linear);
+            }
+            let mut buffer = engine.resize(&buffer, width, height);
+            if linear {
+                Self::map_color_channels(&mut buffer, linear_to_srgb);
+            }
+            DynamicImage::ImageRgb32F(buffer)
+        };
+
+        Self::restore_color_type(resized, image.color())
+    }
+
+    /// Applies `f` to every channel except alpha.
+    fn map_color_channels<P>(buffer: &mut ImageBuffer<P, Vec<f32>>, f: fn(f32) -> f32)
+    where
+        P: Pixel<Subpixel = f32>,
+    {
+        let channels = P::CHANNEL_COUNT as usize;
+        let color_channels = if P::HAS_ALPHA { channels - 1 } else { channels };
+        for pixel in buffer.chunks_exact_mut(channels) {
+            for value in &mut pixel[..color_channels] {
+                *value = f(*value);
+            }
         }
     }
 
-    pub fn resize_exact(&self, image: &DynamicImage, width: u32, height: u32) -> DynamicImage {
-        if width == image.width() && height == image.height() {
+    pub(crate) fn premultiply(buffer: &mut Rgba32FImage) {
+        for pixel in buffer.pixels_mut() {
+            let alpha = pixel[3];
+            for value in &mut pixel.0[..3] {
+                *value *= alpha;
+            }
+        }
+    }
+
+    pub(crate) fn unpremultiply(buffer: &mut Rgba32FImage) {
+        for pixel in buffer.pixels_mut() {
+            // Filters can overshoot, so keep alpha in range before dividing by it
+            let alpha = pixel[3].clamp(0.0, 1.0);
+            pixel[3] = alpha;
+            for value in &mut pixel.0[..3] {
+                *value = if alpha > 0.0 { (*value / alpha).clamp(0.0, 1.0) } else { 0.0 };
+            }
+        }
+    }
+
+    /// Converts a float result back to the color type of the input.
+    pub(crate) fn restore_color_type(image: DynamicImage, color: ColorType) -> DynamicImage {
+        match color {
+            ColorType::L8 => DynamicImage::ImageLuma8(image.to_luma8()),
+            ColorType::La8 => DynamicImage::ImageLumaA8(image.to_luma_alpha8()),
+            ColorType::Rgb8 => DynamicImage::ImageRgb8(image.to_rgb8()),
+            ColorType::Rgba8 => DynamicImage::ImageRgba8(image.to_rgba8()),
+            ColorType::L16 => DynamicImage::ImageLuma16(image.to_luma16()),
+            ColorType::La16 => DynamicImage::ImageLumaA16(image.to_luma_alpha16()),
+            ColorType::Rgb16 => DynamicImage::ImageRgb16(image.to_rgb16()),
+            ColorType::Rgba16 => DynamicImage::ImageRgba16(image.to_rgba16()),
+            ColorType::Rgb32F => DynamicImage::ImageRgb32F(image.to_rgb32f()),
+            _ => image,
+        }
+    }
+
+    /// Scales `image` to cover `width` x `height`, then crops the overflow.
+    pub fn resize_cover(&self, image: &DynamicImage, width: u32, height: u32, gravity: Gravity) -> DynamicImage {
+        let (orig_width, orig_height) = image.dimensions();
+        if width == 0 || height == 0 {
             return image.clone();
         }
 
-        let filter = self.get_filter_type();
-        image.resize_exact(width, height, filter)
+        let ratio = self.policy_ratio(
+            (width as f32 / orig_width as f32).max(height as f32 / orig_height as f32),
+        );
+        let (scaled_width, scaled_height) = (orig_width as f32 * ratio, orig_height as f32 * ratio);
+
+        // When the policy keeps the image too small to cover the target, the largest
+        // window with the target's aspect is kept instead
+        let fit = (scaled_width / width as f32).min(scaled_height / height as f32).min(1.0);
+        let crop_width = ((width as f32 * fit).round() as u32).clamp(1, width);
+        let crop_height = ((height as f32 * fit).round() as u32).clamp(1, height);
+        let scaled_width = (scaled_width.round() as u32).max(crop_width);
+        let scaled_height = (scaled_height.round() as u32).max(crop_height);
+
+        log::debug!(
+            "Covering {}x{} with {}x{} ({:?})",
+            crop_width, crop_height, scaled_width, scaled_height, gravity
+        );
+
+        let scaled = self.resize_exact(image, scaled_width, scaled_height);
+        let (anchor_x, anchor_y) = gravity.anchor();
+        let x = Self::crop_offset(gravity, anchor_x, scaled_width, crop_width);
+        let y = Self::crop_offset(gravity, anchor_y, scaled_height, crop_height);
+
+        scaled.crop_imm(x, y, crop_width, crop_height)
+    }
+
+    /// Fits `image` inside `width` x `height` and pads it to exactly that size.
+    pub fn resize_pad(
+        &self,
+        image: &DynamicImage,
+        width: u32,
+        height: u32,
+        gravity: Gravity,
+        background: Background,
+    ) -> DynamicImage {
+        let (orig_width, orig_height) = image.dimensions();
+        if width == 0 || height == 0 {
+            return image.clone();
+        }
+
+        let ratio = (width as f32 / orig_width as f32).min(height as f32 / orig_height as f32);
+        let fitted = if self.policy_ratio(ratio) == ratio {
+            let (fit_width, fit_height) = self.preserve_aspect(orig_width, orig_height, width, height);
+            self.resize_exact(image, fit_width.min(width), fit_height.min(height))
+        } else {
+            // The policy keeps the original size; a larger image is cropped by the canvas
+            image.clone()
+        };
+
+        // Float, so 16-bit and float inputs keep their precision
+        let mut canvas = match background {
+            Background::Color(color) => {
+                Rgba32FImage::from_pixel(width, height, image::Rgba(color.map(|c| c as f32 / 255.0)))
+            }
+            Background::Transparent => Rgba32FImage::new(width, height),
+            Background::Blur => {
+                let sigma = width.max(height) as f32 / 20.0;
+                self.resize_exact(image, width, height).fast_blur(sigma).to_rgba32f()
+            }
+        };
+
+        let (anchor_x, anchor_y) = gravity.anchor();
+        let x = (anchor_x * (width as f32 - fitted.width() as f32)).round() as i64;
+        let y = (anchor_y * (height as f32 - fitted.height() as f32)).round() as i64;
+        image::imageops::overlay(&mut canvas, &fitted.to_rgba32f(), x, y);
+
+        // Keep opaque results free of an alpha channel so they encode like the source,
+        // at the bit depth of the source
+        let opaque = match background {
+            Background::Color([_, _, _, alpha]) => alpha == 255,
+            Background::Transparent => false,
+            Background::Blur => true,
+        } && !image.color().has_alpha();
+        let bits = image.color().bits_per_pixel() / image.color().channel_count() as u16;
+        let color = match (bits, opaque) {
+            (32, true) => ColorType::Rgb32F,
+            (32, false) => ColorType::Rgba32F,
+            (16, true) => ColorType::Rgb16,
+            (16, false) => ColorType::Rgba16,
+            (_, true) => ColorType::Rgb8,
+            (_, false) => ColorType::Rgba8,
+        };
+        Self::restore_color_type(DynamicImage::ImageRgba32F(canvas), color)
+    }
+
+    /// `ratio` as limited by the policy: shrink-only never enlarges, enlarge-only never shrinks.
+    fn policy_ratio(&self, ratio: f32) -> f32 {
+        match self.policy {
+            ResizePolicy::Always => ratio,
+            ResizePolicy::ShrinkOnly => ratio.min(1.0),
+            ResizePolicy::EnlargeOnly => ratio.max(1.0),
+        }
+    }
+
+    /// Start of the `target` long window kept from a `scaled` long edge.
+    fn crop_offset(gravity: Gravity, anchor: f32, scaled: u32, target: u32) -> u32 {
+        let excess = scaled - target;
+        let offset = match gravity {
+            // Center the window on the focal point, as far as the edges allow
+            Gravity::Focus(..) => anchor * scaled as f32 - target as f32 / 2.0,
+            _ => anchor * excess as f32,
+        };
+        (offset.round().max(0.0) as u32).min(excess)
     }
 
     fn calculate_dimensions(&self, image: &DynamicImage, mode: ResizeMode) -> (u32, u32) {
@@ -94,9 +380,46 @@ impl Resizer {
                 let width = (orig_width as f32 * ratio).round() as u32;
                 (width.max(1), height)
             }
+            ResizeMode::LongestEdge(edge) => {
+                if edge == 0 {
+                    return (orig_width, orig_height);
+                }
+                let ratio = edge as f32 / orig_width.max(orig_height) as f32;
+                Self::scale_dimensions(orig_width, orig_height, ratio)
+            }
+            ResizeMode::ShortestEdge(edge) => {
+                if edge == 0 {
+                    return (orig_width, orig_height);
+                }
+                let ratio = edge as f32 / orig_width.min(orig_height) as f32;
+                Self::scale_dimensions(orig_width, orig_height, ratio)
+            }
+            ResizeMode::MaxPixels(max_pixels) => {
+                let pixels = orig_width as u64 * orig_height as u64;
+                if max_pixels == 0 || pixels <= max_pixels {
+                    return (orig_width, orig_height);
+                }
+                let ratio = (max_pixels as f64 / pixels as f64).sqrt();
+                // Round down so the result never exceeds the budget
+                let width = ((orig_width as f64 * ratio).floor() as u32).max(1);
+                let height = ((orig_height as f64 * ratio).floor() as u32).max(1);
+                (width, height)
+            }
+            ResizeMode::Cover(width, height, _) | ResizeMode::Pad(width, height, _, _) => {
+                if width == 0 || height == 0 {
+                    return (orig_width, orig_height);
+                }
+                (width, height)
+            }
         }
     }
 
+    fn scale_dimensions(width: u32, height: u32, ratio: f32) -> (u32, u32) {
+        let new_width = (width as f32 * ratio).round() as u32;
+        let new_height = (height as f32 * ratio).round() as u32;
+        (new_width.max(1), new_height.max(1))
+    }
+
     fn preserve_aspect(&self, orig_w: u32, orig_h: u32, target_w: u32, target_h: u32) -> (u32, u32) {
         if target_w == 0 && target_h == 0 {
             return (orig_w, orig_h);
@@ -124,20 +447,38 @@ impl Resizer {
         (new_w.max(1), new_h.max(1))
     }
 
-    fn get_filter_type(&self) -> FilterType {
+    /// Convolution kernel for the algorithm; `None` for nearest-neighbour.
+    fn resample_filter(&self) -> Option<ResampleFilter> {
         match self.algorithm {
-            ResizeAlgorithm::Nearest => FilterType::Nearest,
-            ResizeAlgorithm::Bilinear => FilterType::Triangle,
-            ResizeAlgorithm::Bicubic => FilterType::CatmullRom,
-            ResizeAlgorithm::Lanczos3 => FilterType::Lanczos3,
+            ResizeAlgorithm::Nearest => None,
+            ResizeAlgorithm::Bilinear => Some(ResampleFilter::triangle()),
+            ResizeAlgorithm::Bicubic => Some(ResampleFilter::catmull_rom()),
+            ResizeAlgorithm::Lanczos3 => Some(ResampleFilter::lanczos3()),
+            ResizeAlgorithm::Lanczos2 => Some(ResampleFilter::lanczos2()),
+            ResizeAlgorithm::Mitchell => Some(ResampleFilter::mitchell()),
+            ResizeAlgorithm::Gaussian => Some(ResampleFilter::gaussian()),
+            ResizeAlgorithm::Box => Some(ResampleFilter::area()),
+            ResizeAlgorithm::Hermite => Some(ResampleFilter::hermite()),
         }
     }
 
-    pub fn calculate_mode_from_config(width: u32, height: u32, scale: f32) -> ResizeMode {
-        if scale > 0.0 {
-            ResizeMode::Scale(scale)
+    pub fn calculate_mode_from_config(config: &ProcessConfig) -> ResizeMode {
+        let (width, height) = (config.width, config.height);
+
+        if config.scale > 0.0 {
+            ResizeMode::Scale(config.scale)
+        } else if config.longest_edge > 0 {
+            ResizeMode::LongestEdge(config.longest_edge)
+        } else if config.shortest_edge > 0 {
+            ResizeMode::ShortestEdge(config.shortest_edge)
+        } else if config.max_pixels > 0 {
+            ResizeMode::MaxPixels(config.max_pixels)
         } else if width > 0 && height > 0 {
-            ResizeMode::Absolute(width, height)
+            match config.fit {
+                Fit::Inside => ResizeMode::Absolute(width, height),
+                Fit::Cover => ResizeMode::Cover(width, height, config.gravity),
+                Fit::Pad => ResizeMode::Pad(width, height, config.gravity, config.background),
+            }
         } else if width > 0 {
             ResizeMode::Width(width)
         } else if height > 0 {
@@ -146,4 +487,71 @@ impl Resizer {
             ResizeMode::Absolute(0, 0)
         }
     }
-}
\ No newline at end of file
+}
+
+/// Decodes an sRGB-encoded value in 0..=1 to linear light.
+pub(crate) fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Encodes a linear-light value in 0..=1 as sRGB.
+pub(crate) fn linear_to_srgb(value: f32) -> f32 {
+    let value = value.clamp(0.0, 1.0);
+    if value <= 0.003_130_8 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+/// Resizes the image with a fixed [`ResizeMode`].
+pub struct ResizeOperation {
+    resizer: Resizer,
+    mode: ResizeMode,
+}
+
+impl ResizeOperation {
+    pub fn new(resizer: Resizer, mode: ResizeMode) -> Self {
+        Self { resizer, mode }
+    }
+
+    /// Returns `None` when `config` does not ask for any resizing.
+    pub fn from_config(config: &ProcessConfig) -> Option<Self> {
+        let mode = match Resizer::calculate_mode_from_config(config) {
+            ResizeMode::Absolute(0, 0) => return None,
+            // A single dimension goes through `Absolute` so that `keep_aspect` still applies
+            ResizeMode::Width(_) | ResizeMode::Height(_) => {
+                ResizeMode::Absolute(config.width, config.height)
+            }
+            mode => mode,
+        };
+
+        Some(Self::new(Resizer::from_config(config), mode))
+    }
+}
+
+impl Operation for ResizeOperation {
+    fn name(&self) -> &str {
+        "resize"
+    }
+
+    fn apply(&self, image: DynamicImage, context: &mut PipelineContext) -> Result<DynamicImage> {
+        if let Some(reason) = self.resizer.skip_reason(&image, self.mode) {
+            log::info!("Keeping original size: {}", reason);
+            context.resize_skipped = Some(reason);
+            return Ok(image);
+        }
+
+        let resized = self.resizer.resize(&image, self.mode);
+        let (from, to) = (image.dimensions(), resized.dimensions());
+        if from != to {
+            let area = |(w, h): (u32, u32)| w as f64 * h as f64;
+            context.resize_scale = Some((area(to) / area(from)).sqrt());
+        }
+        Ok(resized)
+    }
+}
@@ -0,0 +1,328 @@
+// pixie/src/processors/responsive.rs
+use crate::core::{
+    Fit, ImageToolError, Operation, OutputFormat, Pipeline, PipelineContext, ProcessConfig,
+    ResizePolicy, ResponsiveSpec, Result,
+};
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use rayon::prelude::*;
+use serde::Serialize;
+use std::path::Path;
+use super::{
+    AutoOrientOperation, ConvertToSrgbOperation, LoadedImage, Loader, RedactOperation, ResizeOperation,
+    TransformOperation,
+};
+
+/// One file of a [`ResponsiveSet`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Variant {
+    /// Path relative to the output directory.
+    pub file: String,
+    pub width: u32,
+    pub height: u32,
+    /// MIME type, e.g. `image/webp`.
+    pub mime_type: String,
+    pub bytes: u64,
+}
+
+/// The variants written for one source image; serializes to the JSON manifest.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ResponsiveSet {
+    /// Input file name stem, as used for `{name}`.
+    pub name: String,
+    /// Size of the source after orientation and transforms.
+    pub width: u32,
+    pub height: u32,
+    /// Ordered by format, then by width.
+    pub variants: Vec<Variant>,
+}
+
+impl ResponsiveSet {
+    /// The JSON manifest describing every variant.
+    pub fn manifest(&self) -> Result<String> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| ImageToolError::ProcessingError(format!("Cannot write manifest: {}", e)))
+    }
+
+    /// A `<picture>` element offering every variant through `srcset`.
+    ///
+    /// Formats without universal browser support (WebP) become `<source>` elements; the
+    /// last other format is the `<img>` fallback. File names are prefixed with `url_prefix`.
+    pub fn picture_html(&self, url_prefix: &str, sizes: &str, alt: &str) -> String {
+        let mut mime_types: Vec<&str> = Vec::new();
+        for variant in &self.variants {
+            if !mime_types.contains(&variant.mime_type.as_str()) {
+                mime_types.push(&variant.mime_type);
+            }
+        }
+        let fallback = mime_types
+            .iter()
+            .rev()
+            .find(|&&mime_type| mime_type != "image/webp")
+            .or(mime_types.last())
+            .copied()
+            .unwrap_or_default();
+
+        let url = |variant: &Variant| escape(&format!("{}{}", url_prefix, variant.file).replace(' ', "%20"));
+        let srcset = |mime_type: &str| {
+            self.variants
+                .iter()
+                .filter(|variant| variant.mime_type == mime_type)
+                .map(|variant| format!("{} {}w", url(variant), variant.width))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let mut html = String::from("<picture>\n");
+        for mime_type in mime_types.iter().filter(|&&mime_type| mime_type != fallback) {
+            html.push_str(&format!(
+                "  <source type=\"{}\" srcset=\"{}\" sizes=\"{}\">\n",
+                mime_type,
+                srcset(mime_type),
+                escape(sizes)
+            ));
+        }
+        if let Some(largest) = self
+            .variants
+            .iter()
+            .filter(|variant| variant.mime_type == fallback)
+            .max_by_key(|variant| variant.width)
+        {
+            html.push_str(&format!(
+                "  <img src=\"{}\" srcset=\"{}\" sizes=\"{}\" width=\"{}\" height=\"{}\" alt=\"{}\">\n",
+                url(largest),
+                srcset(fallback),
+                escape(sizes),
+                largest.width,
+                largest.height,
+                escape(alt)
+            ));
+        }
+        html.push_str("</picture>\n");
+        html
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Writes several widths and formats of an image from a single decode.
+///
+/// Orientation, sRGB conversion, redaction and transforms run once on the decoded
+/// image; the remaining steps of `config` run for every variant, in parallel.
+pub struct ResponsiveGenerator {
+    config: ProcessConfig,
+    spec: ResponsiveSpec,
+    loader: Loader,
+}
+
+impl ResponsiveGenerator {
+    /// `config` supplies everything except the output size and format, which come from `spec`.
+    pub fn new(config: ProcessConfig, spec: ResponsiveSpec) -> Self {
+        Self {
+            config,
+            spec,
+            loader: Loader::new(),
+        }
+    }
+
+    /// Decodes `input` and writes its variants into `output_dir`.
+    pub fn generate(&self, input: &Path, output_dir: &Path) -> Result<ResponsiveSet> {
+        let loaded = self.loader.load_with_metadata(input)?;
+        let source_format = self.loader.detect_format(input).ok().or(loaded.format);
+        let name = input.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+
+        let mut context = self.source_context(&loaded, source_format);
+        context.source_path = Some(input.to_path_buf());
+        self.generate_from(loaded.image, context, name, output_dir)
+    }
+
+    /// Writes the variants of an image that is already decoded, named after `name`.
+    pub fn generate_loaded(
+        &self,
+        loaded: LoadedImage,
+        name: &str,
+        output_dir: &Path,
+    ) -> Result<ResponsiveSet> {
+        let context = self.source_context(&loaded, loaded.format);
+        self.generate_from(loaded.image, context, name, output_dir)
+    }
+
+    fn source_context(&self, loaded: &LoadedImage, source_format: Option<ImageFormat>) -> PipelineContext {
+        let mut context = PipelineContext::new(source_format.unwrap_or(ImageFormat::Png))
+            .with_exif(loaded.exif.clone())
+            .with_xmp(loaded.xmp.clone())
+            .with_icc_profile(loaded.icc_profile.clone());
+        context.source_format = source_format;
+        context
+    }
+
+    fn generate_from(
+        &self,
+        image: DynamicImage,
+        mut context: PipelineContext,
+        name: &str,
+        output_dir: &Path,
+    ) -> Result<ResponsiveSet> {
+        self.spec.validate()?;
+        let image = self.prepare().run(image, &mut context)?;
+        let (width, height) = image.dimensions();
+
+        let jobs: Vec<(ImageFormat, u32)> = self
+            .output_formats(context.source_format)?
+            .into_iter()
+            .flat_map(|format| self.widths(width).into_iter().map(move |w| (format, w)))
+            .collect();
+
+        // Built once so the watermark and font are loaded once for every variant
+        let finish = self.finish();
+        std::fs::create_dir_all(output_dir)?;
+        let variants = jobs
+            .par_iter()
+            .map(|&(format, variant_width)| {
+                self.write_variant(&image, &context, &finish, format, variant_width, name, output_dir)
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(ResponsiveSet {
+            name: name.to_string(),
+            width,
+            height,
+            variants,
+        })
+    }
+
+    /// The steps shared by every variant.
+    fn prepare(&self) -> Pipeline {
+        let mut pipeline = Pipeline::new();
+        if self.config.auto_orient {
+            pipeline.push(AutoOrientOperation::new());
+        }
+        if self.config.convert_to_srgb {
+            pipeline.push(ConvertToSrgbOperation::new());
+        }
+        if let Some(redact) = RedactOperation::from_config(&self.config) {
+            pipeline.push(redact);
+        }
+        if let Some(transform) = TransformOperation::from_config(&self.config) {
+            pipeline.push(transform);
+        }
+        pipeline
+    }
+
+    /// The steps after resizing, shared by every variant: everything in `config` except
+    /// the ones in [`Self::prepare`] and the resize itself.
+    fn finish(&self) -> Pipeline {
+        let config = ProcessConfig {
+            width: 0,
+            height: 0,
+            scale: 0.0,
+            longest_edge: 0,
+            shortest_edge: 0,
+            max_pixels: 0,
+            auto_orient: false,
+            convert_to_srgb: false,
+            redaction: None,
+            transforms: Vec::new(),
+            ..self.config.clone()
+        };
+        Pipeline::from_config(&config)
+    }
+
+    /// The requested formats with `SameAsInput` resolved, in order and without
+    /// duplicates, so `png,same` on a PNG writes each file once.
+    fn output_formats(&self, source_format: Option<ImageFormat>) -> Result<Vec<ImageFormat>> {
+        let mut formats = Vec::new();
+        for &format in &self.spec.formats {
+            let format = match format {
+                OutputFormat::Jpeg => ImageFormat::Jpeg,
+                OutputFormat::Png => ImageFormat::Png,
+                OutputFormat::WebP => ImageFormat::WebP,
+                OutputFormat::SameAsInput => source_format.ok_or_else(|| {
+                    ImageToolError::UnsupportedFormat(
+                        "Cannot determine output format from input; specify one explicitly".to_string(),
+                    )
+                })?,
+            };
+            if !formats.contains(&format) {
+                formats.push(format);
+            }
+        }
+        Ok(formats)
+    }
+
+    /// Requested widths up to the source width, ascending; widths above it are replaced
+    /// by the source width so the largest variant keeps every pixel.
+    fn widths(&self, source_width: u32) -> Vec<u32> {
+        let mut widths: Vec<u32> = self
+            .spec
+            .widths
+            .iter()
+            .copied()
+            .filter(|&width| width <= source_width)
+            .collect();
+        if widths.len() < self.spec.widths.len() {
+            log::info!("Writing the source width of {}px instead of larger widths", source_width);
+            widths.push(source_width);
+        }
+        widths.sort_unstable();
+        widths.dedup();
+        widths
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn write_variant(
+        &self,
+        image: &DynamicImage,
+        source: &PipelineContext,
+        finish: &Pipeline,
+        output_format: ImageFormat,
+        width: u32,
+        name: &str,
+        output_dir: &Path,
+    ) -> Result<Variant> {
+        let resize_config = ProcessConfig {
+            width,
+            height: 0,
+            scale: 0.0,
+            longest_edge: 0,
+            shortest_edge: 0,
+            max_pixels: 0,
+            keep_aspect: true,
+            fit: Fit::Inside,
+            resize_policy: ResizePolicy::Always,
+            ..self.config.clone()
+        };
+        let mut context = PipelineContext {
+            output_format,
+            encoded: None,
+            ..source.clone()
+        };
+        let mut resized = image.clone();
+        if let Some(resize) = ResizeOperation::from_config(&resize_config) {
+            resized = resize.apply(resized, &mut context)?;
+        }
+        let resized = finish.run(resized, &mut context)?;
+        let encoded = context
+            .encoded
+            .ok_or_else(|| ImageToolError::ProcessingError("Pipeline produced no output".to_string()))?;
+
+        let extension = output_format.extensions_str().first().copied().unwrap_or("img");
+        let file = self.spec.file_name(name, resized.width(), resized.height(), extension)?;
+        let path = output_dir.join(&file);
+        std::fs::write(&path, &encoded)?;
+        log::debug!("Wrote {} ({} bytes)", path.display(), encoded.len());
+
+        Ok(Variant {
+            file,
+            width: resized.width(),
+            height: resized.height(),
+            mime_type: output_format.to_mime_type().to_string(),
+            bytes: encoded.len() as u64,
+        })
+    }
+}
@@ -0,0 +1,91 @@
+// pixie/src/processors/sharpen.rs
+use crate::core::{Operation, PipelineContext, ProcessConfig, Result, Sharpen, UnsharpMask};
+use image::{imageops, DynamicImage};
+use super::Resizer;
+
+/// Unsharp masking: adds back the difference between the image and a blurred copy.
+pub struct Sharpener;
+
+impl Sharpener {
+    /// Mask used by [`Sharpen::Auto`] after shrinking by `reduction` (input / output
+    /// size, per side); `None` when the image was not shrunk.
+    pub fn auto_mask(reduction: f64) -> Option<UnsharpMask> {
+        if reduction <= 1.0 {
+            return None;
+        }
+        Some(UnsharpMask {
+            radius: 0.7,
+            amount: (0.4 * reduction.log2()).min(1.0) as f32,
+            threshold: 2,
+        })
+    }
+
+    /// Sharpens the color channels of `image`, keeping its color type; alpha is untouched.
+    pub fn unsharp_mask(image: &DynamicImage, mask: UnsharpMask) -> DynamicImage {
+        if mask.amount <= 0.0 {
+            return image.clone();
+        }
+
+        let has_alpha = image.color().has_alpha();
+        let threshold = mask.threshold as f32 / 255.0;
+        let mut sharpened = image.to_rgba32f();
+        let blurred = imageops::blur(&sharpened, mask.radius);
+
+        for (pixel, blurred) in sharpened.pixels_mut().zip(blurred.pixels()) {
+            for c in 0..3 {
+                let difference = pixel[c] - blurred[c];
+                if difference.abs() >= threshold {
+                    pixel[c] = (pixel[c] + mask.amount * difference).clamp(0.0, 1.0);
+                }
+            }
+        }
+
+        let sharpened = if has_alpha {
+            DynamicImage::ImageRgba32F(sharpened)
+        } else {
+            DynamicImage::ImageRgb32F(DynamicImage::ImageRgba32F(sharpened).to_rgb32f())
+        };
+        Resizer::restore_color_type(sharpened, image.color())
+    }
+}
+
+/// Sharpens the image after resizing.
+///
+/// [`Sharpen::Auto`] reads the reduction from `PipelineContext::resize_scale` and does
+/// nothing when the image was not shrunk.
+pub struct SharpenOperation {
+    sharpen: Sharpen,
+}
+
+impl SharpenOperation {
+    pub fn new(sharpen: Sharpen) -> Self {
+        Self { sharpen }
+    }
+
+    pub fn from_config(config: &ProcessConfig) -> Option<Self> {
+        config.sharpen.map(Self::new)
+    }
+}
+
+impl Operation for SharpenOperation {
+    fn name(&self) -> &str {
+        "sharpen"
+    }
+
+    fn apply(&self, image: DynamicImage, context: &mut PipelineContext) -> Result<DynamicImage> {
+        let mask = match self.sharpen {
+            Sharpen::Mask(mask) => Some(mask),
+            Sharpen::Auto => context
+                .resize_scale
+                .and_then(|scale| Sharpener::auto_mask(1.0 / scale)),
+        };
+
+        match mask {
+            Some(mask) => {
+                log::debug!("Sharpening with {:?}", mask);
+                Ok(Sharpener::unsharp_mask(&image, mask))
+            }
+            None => Ok(image),
+        }
+    }
+}
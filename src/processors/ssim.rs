@@ -0,0 +1,88 @@
+// pixie/src/processors/ssim.rs
+use crate::core::{ImageToolError, Result};
+use image::{DynamicImage, GenericImageView, RgbImage};
+
+const WINDOW: u32 = 8;
+const STRIDE: u32 = 4;
+const C1: f64 = (0.01 * 255.0) * (0.01 * 255.0);
+const C2: f64 = (0.03 * 255.0) * (0.03 * 255.0);
+
+/// Structural similarity between two images of the same size, from 0 to 1 (identical).
+///
+/// Computed per RGB channel over 8x8 windows with a stride of 4 and averaged, so
+/// chroma damage counts as much as luma damage. Alpha is ignored.
+pub fn ssim(reference: &DynamicImage, candidate: &DynamicImage) -> Result<f64> {
+    if reference.dimensions() != candidate.dimensions() {
+        return Err(ImageToolError::InvalidParameter(format!(
+            "Cannot compare images of different sizes: {:?} and {:?}",
+            reference.dimensions(),
+            candidate.dimensions()
+        )));
+    }
+
+    let reference = reference.to_rgb8();
+    let candidate = candidate.to_rgb8();
+
+    let score = (0..3)
+        .map(|channel| channel_ssim(&reference, &candidate, channel))
+        .sum::<f64>()
+        / 3.0;
+
+    Ok(score)
+}
+
+fn channel_ssim(reference: &RgbImage, candidate: &RgbImage, channel: usize) -> f64 {
+    let (width, height) = reference.dimensions();
+    let window_width = WINDOW.min(width);
+    let window_height = WINDOW.min(height);
+
+    let mut total = 0.0;
+    let mut windows = 0usize;
+
+    let mut y = 0;
+    while y + window_height <= height {
+        let mut x = 0;
+        while x + window_width <= width {
+            total += window_ssim(reference, candidate, channel, x, y, window_width, window_height);
+            windows += 1;
+            x += STRIDE;
+        }
+        y += STRIDE;
+    }
+
+    total / windows.max(1) as f64
+}
+
+fn window_ssim(
+    reference: &RgbImage,
+    candidate: &RgbImage,
+    channel: usize,
+    x0: u32,
+    y0: u32,
+    width: u32,
+    height: u32,
+) -> f64 {
+    let count = (width * height) as f64;
+    let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0.0, 0.0, 0.0, 0.0, 0.0);
+
+    for y in y0..y0 + height {
+        for x in x0..x0 + width {
+            let a = reference.get_pixel(x, y).0[channel] as f64;
+            let b = candidate.get_pixel(x, y).0[channel] as f64;
+            sum_a += a;
+            sum_b += b;
+            sum_aa += a * a;
+            sum_bb += b * b;
+            sum_ab += a * b;
+        }
+    }
+
+    let mean_a = sum_a / count;
+    let mean_b = sum_b / count;
+    let var_a = sum_aa / count - mean_a * mean_a;
+    let var_b = sum_bb / count - mean_b * mean_b;
+    let covariance = sum_ab / count - mean_a * mean_b;
+
+    ((2.0 * mean_a * mean_b + C1) * (2.0 * covariance + C2))
+        / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2))
+}
@@ -0,0 +1,169 @@
+// pixie/src/processors/text.rs
+use crate::core::{ImageToolError, Operation, PipelineContext, ProcessConfig, Result, TextOverlay};
+use ab_glyph::{Font, FontArc, PxScale, ScaleFont};
+use image::{DynamicImage, GrayImage, Luma, Rgba32FImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use imageproc::morphology::{grayscale_dilate, Mask};
+use std::path::Path;
+use std::sync::OnceLock;
+use super::Resizer;
+
+/// DejaVu Sans, used when no font file is given; its license is next to it in assets/fonts.
+const BUNDLED_FONT: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+
+/// Draws text onto images.
+pub struct TextRenderer;
+
+impl TextRenderer {
+    pub fn bundled_font() -> FontArc {
+        FontArc::try_from_slice(BUNDLED_FONT).expect("the bundled font is valid")
+    }
+
+    /// Reads a TrueType or OpenType font file.
+    pub fn load_font(path: &Path) -> Result<FontArc> {
+        let data = std::fs::read(path)?;
+        FontArc::try_from_vec(data).map_err(|e| {
+            ImageToolError::InvalidParameter(format!("Cannot load font {}: {}", path.display(), e))
+        })
+    }
+
+    /// Draws `overlay` onto `image` with `font` (the overlay's font path is not used).
+    pub fn apply(image: &DynamicImage, font: &FontArc, overlay: &TextOverlay) -> DynamicImage {
+        let fill = Self::coverage(font, overlay);
+        let stroke = (overlay.stroke_width > 0)
+            .then(|| grayscale_dilate(&fill, &Mask::disk(overlay.stroke_width as u8)));
+
+        let mut canvas = image.to_rgba32f();
+        let (x, y) = Self::position(&canvas, &fill, overlay);
+
+        // The shadow is cast by the outline when there is one
+        if let Some((dx, dy)) = overlay.shadow {
+            let caster = stroke.as_ref().unwrap_or(&fill);
+            blend(&mut canvas, caster, x + dx as i64, y + dy as i64, overlay.shadow_color);
+        }
+        if let Some(stroke) = &stroke {
+            blend(&mut canvas, stroke, x, y, overlay.stroke_color);
+        }
+        blend(&mut canvas, &fill, x, y, overlay.color);
+
+        log::debug!("Drew {:?} at ({}, {})", overlay.text, x, y);
+        Resizer::restore_color_type(DynamicImage::ImageRgba32F(canvas), image.color())
+    }
+
+    /// Coverage of the text lines, surrounded by room for the outline.
+    fn coverage(font: &FontArc, overlay: &TextOverlay) -> GrayImage {
+        let scale = PxScale::from(overlay.size);
+        let metrics = font.as_scaled(scale);
+        let line_height = metrics.height() + metrics.line_gap();
+
+        let lines: Vec<(&str, u32)> = overlay
+            .text
+            .lines()
+            .map(|line| (line, text_size(scale, font, line).0))
+            .collect();
+        // `text_size` truncates, so leave a pixel for the last glyph's antialiasing
+        let text_width = lines.iter().map(|&(_, width)| width).max().unwrap_or(0) + 1;
+        let text_height = (line_height * (lines.len().max(1) - 1) as f32 + metrics.height()).ceil() as u32;
+
+        let pad = overlay.stroke_width;
+        let mut coverage = GrayImage::new(text_width + 2 * pad, text_height + 2 * pad);
+        let (anchor_x, _) = overlay.gravity.anchor();
+        for (i, (line, width)) in lines.into_iter().enumerate() {
+            let x = pad as f32 + (text_width - width) as f32 * anchor_x;
+            let y = pad as f32 + line_height * i as f32;
+            draw_text_mut(&mut coverage, Luma([255]), x.round() as i32, y.round() as i32, scale, font, line);
+        }
+        coverage
+    }
+
+    /// Top-left corner of the text block, outline included.
+    fn position(canvas: &Rgba32FImage, block: &GrayImage, overlay: &TextOverlay) -> (i64, i64) {
+        let (anchor_x, anchor_y) = overlay.gravity.anchor();
+        let margin = overlay.margin as i64;
+        let free_x = (canvas.width() as i64 - 2 * margin - block.width() as i64).max(0) as f32;
+        let free_y = (canvas.height() as i64 - 2 * margin - block.height() as i64).max(0) as f32;
+        (
+            margin + (free_x * anchor_x).round() as i64,
+            margin + (free_y * anchor_y).round() as i64,
+        )
+    }
+}
+
+/// Paints `color` onto `canvas` through `coverage`, whose top-left corner goes at (`x`, `y`).
+fn blend(canvas: &mut Rgba32FImage, coverage: &GrayImage, x: i64, y: i64, color: [u8; 4]) {
+    let color = color.map(|c| c as f32 / 255.0);
+    let (width, height) = (canvas.width() as i64, canvas.height() as i64);
+
+    for (cx, cy, value) in coverage.enumerate_pixels() {
+        let (px, py) = (x + cx as i64, y + cy as i64);
+        if px < 0 || py < 0 || px >= width || py >= height {
+            continue;
+        }
+        let alpha = value[0] as f32 / 255.0 * color[3];
+        if alpha == 0.0 {
+            continue;
+        }
+
+        let pixel = canvas.get_pixel_mut(px as u32, py as u32);
+        let below = pixel[3] * (1.0 - alpha);
+        let out = alpha + below;
+        for c in 0..3 {
+            pixel[c] = (color[c] * alpha + pixel[c] * below) / out;
+        }
+        pixel[3] = out;
+    }
+}
+
+/// Draws a [`TextOverlay`] onto the image.
+///
+/// The font is read the first time the operation runs and kept for every later image,
+/// so a batch sharing one pipeline loads it once.
+pub struct TextOverlayOperation {
+    overlay: TextOverlay,
+    font: OnceLock<std::result::Result<FontArc, String>>,
+}
+
+impl TextOverlayOperation {
+    pub fn new(overlay: TextOverlay) -> Self {
+        Self {
+            overlay,
+            font: OnceLock::new(),
+        }
+    }
+
+    /// Uses an already loaded font instead of reading `overlay.font`.
+    pub fn with_font(overlay: TextOverlay, font: FontArc) -> Self {
+        Self {
+            overlay,
+            font: OnceLock::from(Ok(font)),
+        }
+    }
+
+    pub fn from_config(config: &ProcessConfig) -> Option<Self> {
+        config.text_overlay.clone().map(Self::new)
+    }
+
+    fn font(&self) -> Result<&FontArc> {
+        self.font
+            .get_or_init(|| match &self.overlay.font {
+                Some(path) => {
+                    log::debug!("Loading font from {}", path.display());
+                    TextRenderer::load_font(path).map_err(|e| e.to_string())
+                }
+                None => Ok(TextRenderer::bundled_font()),
+            })
+            .as_ref()
+            .map_err(|e| ImageToolError::ProcessingError(e.clone()))
+    }
+}
+
+impl Operation for TextOverlayOperation {
+    fn name(&self) -> &str {
+        "text"
+    }
+
+    fn apply(&self, image: DynamicImage, _context: &mut PipelineContext) -> Result<DynamicImage> {
+        let font = self.font()?;
+        Ok(TextRenderer::apply(&image, font, &self.overlay))
+    }
+}
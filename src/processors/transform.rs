@@ -0,0 +1,135 @@
+// pixie/src/processors/transform.rs
+use crate::core::{Operation, PipelineContext, ProcessConfig, Result, Transform};
+use image::{imageops, ColorType, DynamicImage, GenericImageView, Rgba, Rgba32FImage};
+use imageproc::geometric_transformations::{warp_into, Interpolation, Projection};
+use super::Resizer;
+
+/// Padding around the source of an arbitrary rotation.
+const BORDER: u32 = 2;
+
+/// Rotates and mirrors images.
+pub struct Transformer;
+
+impl Transformer {
+    pub fn apply(image: &DynamicImage, transform: Transform) -> DynamicImage {
+        match transform {
+            Transform::Rotate90 => image.rotate90(),
+            Transform::Rotate180 => image.rotate180(),
+            Transform::Rotate270 => image.rotate270(),
+            Transform::FlipHorizontal => image.fliph(),
+            Transform::FlipVertical => image.flipv(),
+            Transform::Transpose => image.rotate90().fliph(),
+            Transform::Transverse => image.rotate270().fliph(),
+            Transform::Rotate { degrees, background, crop } => Self::rotate(image, degrees, background, crop),
+        }
+    }
+
+    /// Rotates clockwise by `degrees`; right angles are exact and never need a fill.
+    pub fn rotate(image: &DynamicImage, degrees: f32, background: [u8; 4], crop: bool) -> DynamicImage {
+        let degrees = degrees.rem_euclid(360.0);
+        let quarter_turns = degrees / 90.0;
+        if (quarter_turns - quarter_turns.round()).abs() < 1e-4 {
+            return match quarter_turns.round() as u32 % 4 {
+                1 => image.rotate90(),
+                2 => image.rotate180(),
+                3 => image.rotate270(),
+                _ => image.clone(),
+            };
+        }
+
+        let (width, height) = image.dimensions();
+        let theta = degrees.to_radians();
+        let (sin, cos) = (theta.sin().abs(), theta.cos().abs());
+        let canvas_width = (width as f32 * cos + height as f32 * sin).ceil() as u32;
+        let canvas_height = (width as f32 * sin + height as f32 * cos).ceil() as u32;
+
+        // Premultiplied, so the fill color does not bleed into the edges when interpolating
+        let mut pixels = image.to_rgba32f();
+        Resizer::premultiply(&mut pixels);
+        let alpha = background[3] as f32 / 255.0;
+        let fill = Rgba([0, 1, 2, 3].map(|c| {
+            let value = background[c] as f32 / 255.0;
+            if c == 3 { value } else { value * alpha }
+        }));
+
+        // Bicubic sampling gives up within two pixels of the border, so surround the
+        // image with fill to keep its edges and let them blend smoothly into the corners
+        let mut source = Rgba32FImage::from_pixel(width + 2 * BORDER, height + 2 * BORDER, fill);
+        imageops::replace(&mut source, &pixels, BORDER as i64, BORDER as i64);
+
+        let projection = Projection::translate(canvas_width as f32 / 2.0, canvas_height as f32 / 2.0)
+            * Projection::rotate(theta)
+            * Projection::translate(-(source.width() as f32) / 2.0, -(source.height() as f32) / 2.0);
+        let mut rotated = Rgba32FImage::new(canvas_width, canvas_height);
+        warp_into(&source, &projection, Interpolation::Bicubic, fill, &mut rotated);
+        Resizer::unpremultiply(&mut rotated);
+
+        let mut rotated = DynamicImage::ImageRgba32F(rotated);
+        if crop {
+            let (crop_width, crop_height) = Self::inscribed_size(width, height, theta);
+            let x = (canvas_width - crop_width) / 2;
+            let y = (canvas_height - crop_height) / 2;
+            rotated = rotated.crop_imm(x, y, crop_width, crop_height);
+        }
+
+        // Only keep an alpha channel the source did not have when the fill needs it
+        let color = image.color();
+        if color.has_alpha() || crop || background[3] == 255 {
+            return Resizer::restore_color_type(rotated, color);
+        }
+        match color {
+            ColorType::L16 | ColorType::Rgb16 => DynamicImage::ImageRgba16(rotated.to_rgba16()),
+            ColorType::Rgb32F => rotated,
+            _ => DynamicImage::ImageRgba8(rotated.to_rgba8()),
+        }
+    }
+
+    /// Size of the largest axis-aligned rectangle inside a `width` x `height` image
+    /// rotated by `theta`, inset by a pixel so no interpolated edge remains.
+    fn inscribed_size(width: u32, height: u32, theta: f32) -> (u32, u32) {
+        let (w, h) = (width as f32, height as f32);
+        let (sin, cos) = (theta.sin().abs(), theta.cos().abs());
+        let (long, short) = if w >= h { (w, h) } else { (h, w) };
+
+        let (crop_width, crop_height) = if short <= 2.0 * sin * cos * long || (sin - cos).abs() < 1e-6 {
+            // Two corners of the rectangle touch the long sides
+            let half = 0.5 * short;
+            if w >= h { (half / sin, half / cos) } else { (half / cos, half / sin) }
+        } else {
+            let cos_2a = cos * cos - sin * sin;
+            ((w * cos - h * sin) / cos_2a, (h * cos - w * sin) / cos_2a)
+        };
+
+        let inset = |size: f32| ((size - 2.0).floor() as u32).max(1);
+        (inset(crop_width), inset(crop_height))
+    }
+}
+
+/// Applies a list of rotations and flips in order.
+pub struct TransformOperation {
+    transforms: Vec<Transform>,
+}
+
+impl TransformOperation {
+    pub fn new(transforms: Vec<Transform>) -> Self {
+        Self { transforms }
+    }
+
+    /// Returns `None` when `config` has no transforms.
+    pub fn from_config(config: &ProcessConfig) -> Option<Self> {
+        (!config.transforms.is_empty()).then(|| Self::new(config.transforms.clone()))
+    }
+}
+
+impl Operation for TransformOperation {
+    fn name(&self) -> &str {
+        "transform"
+    }
+
+    fn apply(&self, image: DynamicImage, _context: &mut PipelineContext) -> Result<DynamicImage> {
+        Ok(self
+            .transforms
+            .iter()
+            .fold(image, |image, transform| Transformer::apply(&image, *transform)))
+    }
+}
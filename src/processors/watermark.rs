@@ -0,0 +1,149 @@
+// pixie/src/processors/watermark.rs
+use crate::core::{
+    ImageToolError, Operation, PipelineContext, ProcessConfig, ResizeAlgorithm, Result, Watermark,
+    WatermarkLayout,
+};
+use image::{imageops, DynamicImage, GenericImageView, Rgba32FImage};
+use std::sync::OnceLock;
+use super::{Resizer, Transformer};
+
+/// Tilt of [`WatermarkLayout::Diagonal`] copies, clockwise.
+const DIAGONAL_DEGREES: f32 = -30.0;
+
+/// Most copies a tiled layout composites; tiny logos are spread out to stay below it.
+const MAX_TILES: f64 = 4096.0;
+
+/// Composites logos onto images.
+pub struct Watermarker;
+
+impl Watermarker {
+    /// Draws `logo` onto `image` as described by `watermark` (its path is not used).
+    pub fn apply(image: &DynamicImage, logo: &DynamicImage, watermark: &Watermark) -> DynamicImage {
+        let (width, height) = image.dimensions();
+        let logo = Self::prepare_logo(logo, width, watermark);
+
+        let mut canvas = image.to_rgba32f();
+        for (x, y) in Self::positions(&canvas, &logo, watermark) {
+            imageops::overlay(&mut canvas, &logo, x, y);
+        }
+
+        log::debug!("Watermarked {}x{} image", width, height);
+        Resizer::restore_color_type(DynamicImage::ImageRgba32F(canvas), image.color())
+    }
+
+    /// Scales the logo to the image, applies the opacity and tilts diagonal copies.
+    fn prepare_logo(logo: &DynamicImage, image_width: u32, watermark: &Watermark) -> Rgba32FImage {
+        let mut logo = logo.clone();
+
+        if watermark.scale > 0.0 {
+            let (logo_width, logo_height) = logo.dimensions();
+            let width = ((image_width as f32 * watermark.scale).round() as u32).max(1);
+            let height = ((logo_height as f32 * width as f32 / logo_width as f32).round() as u32).max(1);
+            logo = Resizer::new(ResizeAlgorithm::Lanczos3, false).resize_exact(&logo, width, height);
+        }
+
+        if watermark.layout == WatermarkLayout::Diagonal {
+            logo = Transformer::rotate(&logo, DIAGONAL_DEGREES, [0, 0, 0, 0], false);
+        }
+
+        let mut logo = logo.to_rgba32f();
+        for pixel in logo.pixels_mut() {
+            pixel[3] *= watermark.opacity;
+        }
+        logo
+    }
+
+    /// Top-left corners of every copy of `logo`; copies may hang over the edges.
+    fn positions(canvas: &Rgba32FImage, logo: &Rgba32FImage, watermark: &Watermark) -> Vec<(i64, i64)> {
+        let (width, height) = (canvas.width() as i64, canvas.height() as i64);
+        let (logo_width, logo_height) = (logo.width() as i64, logo.height() as i64);
+        let margin = watermark.margin as i64;
+
+        if watermark.layout == WatermarkLayout::Single {
+            let (anchor_x, anchor_y) = watermark.gravity.anchor();
+            let free_x = (width - 2 * margin - logo_width).max(0) as f32;
+            let free_y = (height - 2 * margin - logo_height).max(0) as f32;
+            return vec![(
+                margin + (free_x * anchor_x).round() as i64,
+                margin + (free_y * anchor_y).round() as i64,
+            )];
+        }
+
+        let mut step_x = logo_width + margin;
+        let mut step_y = logo_height + margin;
+        let tiles = (width as f64 / step_x as f64).ceil() * (height as f64 / step_y as f64).ceil();
+        if tiles > MAX_TILES {
+            let spread = (tiles / MAX_TILES).sqrt();
+            step_x = (step_x as f64 * spread).ceil() as i64;
+            step_y = (step_y as f64 * spread).ceil() as i64;
+            log::warn!("Watermark is too small to tile {} times, spacing copies {}x{} apart", tiles, step_x, step_y);
+        }
+        let mut positions = Vec::new();
+        for (row, y) in (0..height).step_by(step_y as usize).enumerate() {
+            // Diagonal rows are staggered by half a copy
+            let offset = match watermark.layout {
+                WatermarkLayout::Diagonal if row % 2 == 1 => -step_x / 2,
+                _ => 0,
+            };
+            let mut x = offset;
+            while x < width {
+                positions.push((x, y));
+                x += step_x;
+            }
+        }
+        positions
+    }
+}
+
+/// Composites a watermark onto the image.
+///
+/// The logo is read the first time the operation runs and kept for every later image,
+/// so a batch sharing one pipeline loads it once.
+pub struct WatermarkOperation {
+    watermark: Watermark,
+    logo: OnceLock<std::result::Result<DynamicImage, String>>,
+}
+
+impl WatermarkOperation {
+    pub fn new(watermark: Watermark) -> Self {
+        Self {
+            watermark,
+            logo: OnceLock::new(),
+        }
+    }
+
+    /// Uses an already decoded logo instead of reading `watermark.path`.
+    pub fn with_logo(watermark: Watermark, logo: DynamicImage) -> Self {
+        Self {
+            watermark,
+            logo: OnceLock::from(Ok(logo)),
+        }
+    }
+
+    pub fn from_config(config: &ProcessConfig) -> Option<Self> {
+        config.watermark.clone().map(Self::new)
+    }
+
+    fn logo(&self) -> Result<&DynamicImage> {
+        self.logo
+            .get_or_init(|| {
+                log::debug!("Loading watermark from {}", self.watermark.path.display());
+                image::open(&self.watermark.path).map_err(|e| {
+                    format!("Cannot load watermark {}: {}", self.watermark.path.display(), e)
+                })
+            })
+            .as_ref()
+            .map_err(|e| ImageToolError::ProcessingError(e.clone()))
+    }
+}
+
+impl Operation for WatermarkOperation {
+    fn name(&self) -> &str {
+        "watermark"
+    }
+
+    fn apply(&self, image: DynamicImage, _context: &mut PipelineContext) -> Result<DynamicImage> {
+        let logo = self.logo()?;
+        Ok(Watermarker::apply(&image, logo, &self.watermark))
+    }
+}
@@ -3,6 +3,11 @@ use crate::core::{ImageToolError, Result};
 use std::path::{Path, PathBuf};
 use std::time::SystemTime;
 
+/// Returns true for the conventional `-` path meaning stdin or stdout.
+pub fn is_stdio_path(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
 pub fn generate_output_path(
     input_path: &Path,
     output: Option<&Path>,
@@ -10,6 +15,8 @@ pub fn generate_output_path(
 ) -> PathBuf {
     match output {
         Some(path) => path.to_path_buf(),
+        // Data read from stdin is written back to stdout unless told otherwise
+        None if is_stdio_path(input_path) => PathBuf::from("-"),
         None => {
             let stem = input_path
                 .file_stem()
@@ -54,6 +61,31 @@ pub fn format_file_size(bytes: u64) -> String {
     format!("{:.2} {}", size, UNITS[exponent as usize])
 }
 
+/// Parses sizes such as `500`, `200KB`, `200k` or `1.5MB` (1 KB = 1024 bytes, like
+/// [`format_file_size`]).
+pub fn parse_file_size(value: &str) -> Result<u64> {
+    const UNITS: [(&str, u64); 7] = [
+        ("kb", 1 << 10), ("k", 1 << 10),
+        ("mb", 1 << 20), ("m", 1 << 20),
+        ("gb", 1 << 30), ("g", 1 << 30),
+        ("b", 1),
+    ];
+
+    let lower = value.trim().to_lowercase();
+    let (number, multiplier) = UNITS
+        .iter()
+        .find_map(|(suffix, multiplier)| lower.strip_suffix(suffix).map(|n| (n, *multiplier)))
+        .unwrap_or((lower.as_str(), 1));
+
+    let invalid = || ImageToolError::InvalidParameter(format!("Invalid file size: {}", value));
+    let number: f64 = number.trim().parse().map_err(|_| invalid())?;
+    if !number.is_finite() || number <= 0.0 {
+        return Err(invalid());
+    }
+
+    Ok((number * multiplier as f64).round() as u64)
+}
+
 pub fn calculate_aspect_ratio(width: u32, height: u32) -> f32 {
     if height == 0 {
         0.0
@@ -82,7 +114,7 @@ pub fn get_image_info(path: &Path) -> Result<(u32, u32, String)> {
     use image::ImageFormat;
     
     let file = std::fs::File::open(path)?;
-    let reader = image::io::Reader::new(std::io::BufReader::new(file))
+    let reader = image::ImageReader::new(std::io::BufReader::new(file))
         .with_guessed_format()?;
 
     let format = match reader.format() {
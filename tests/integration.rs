@@ -2,20 +2,29 @@
 mod tests {
     use assert_fs::prelude::*;
     use assert_fs::TempDir;
-    use image_tool_rs::{ImageProcessor, ProcessConfig, ResizeAlgorithm};
+    use image::{DynamicImage, GenericImageView};
+    use pixie::{
+        Adjuster, Adjustments, Background, BatchProcessor, CompressOperation, Compressor,
+        ContactSheet, ContactSheetMaker, ConvolutionResizer, Fit, Gravity, ImageProcessor,
+        MetadataRules, Operation, Pipeline, PipelineContext, ProcessConfig, RedactStyle, Redaction,
+        Redactor, Region, ResampleFilter, ResizeAlgorithm, ResizeMode, ResizeOperation,
+        ResizePolicy, Resizer, ResponsiveGenerator, ResponsiveSpec, Sharpen, Sharpener, TextOverlay,
+        TextRenderer, Transform, Transformer, UnsharpMask, Watermark, WatermarkLayout, Watermarker,
+        ssim,
+    };
     use std::fs;
 
     #[test]
     fn test_resize_image() {
         let temp_dir = TempDir::new().unwrap();
         let input_path = temp_dir.child("test.jpg");
-        
+
         // Create a simple test image (1x1 pixel)
         let img = image::RgbImage::new(1, 1);
         img.save(input_path.path()).unwrap();
-        
+
         let output_path = temp_dir.child("output.jpg");
-        
+
         let config = ProcessConfig {
             width: 100,
             height: 100,
@@ -24,22 +33,1518 @@ mod tests {
             keep_aspect: true,
             strip_metadata: false,
             algorithm: ResizeAlgorithm::Lanczos3,
+            ..Default::default()
         };
-        
+
         let processor = ImageProcessor::new(config);
         let result = processor.process(input_path.path(), output_path.path());
-        
+
         assert!(result.is_ok());
         assert!(output_path.path().exists());
     }
-    
+
     #[test]
     fn test_invalid_file() {
         let config = ProcessConfig::default();
         let processor = ImageProcessor::new(config);
-        
+
         let result = processor.process("nonexistent.jpg", "output.jpg");
-        
+
         assert!(result.is_err());
     }
-}
\ No newline at end of file
+
+    struct Invert;
+
+    impl Operation for Invert {
+        fn name(&self) -> &str {
+            "invert"
+        }
+
+        fn apply(&self, mut image: DynamicImage, _context: &mut PipelineContext) -> pixie::Result<DynamicImage> {
+            image.invert();
+            Ok(image)
+        }
+    }
+
+    #[test]
+    fn test_custom_pipeline() {
+        let temp_dir = TempDir::new().unwrap();
+        let input_path = temp_dir.child("black.png");
+        image::RgbImage::new(40, 20).save(input_path.path()).unwrap();
+        let output_path = temp_dir.child("white.png");
+
+        let pipeline = Pipeline::new()
+            .with_operation(ResizeOperation::new(
+                Resizer::new(ResizeAlgorithm::Nearest, true),
+                ResizeMode::Width(10),
+            ))
+            .with_operation(Invert)
+            .with_operation(CompressOperation::new(Compressor::new(90)));
+        assert_eq!(pipeline.operation_names(), vec!["resize", "invert", "compress"]);
+
+        let processor = ImageProcessor::with_pipeline(ProcessConfig::default(), pipeline);
+        processor.process(input_path.path(), output_path.path()).unwrap();
+
+        let output = image::open(output_path.path()).unwrap();
+        assert_eq!(output.dimensions(), (10, 5));
+        assert_eq!(output.to_rgb8().get_pixel(0, 0).0, [255, 255, 255]);
+    }
+
+    #[test]
+    fn test_batch_runs_shared_pipeline() {
+        let input_dir = TempDir::new().unwrap();
+        let output_dir = TempDir::new().unwrap();
+        for name in ["a.png", "b.png"] {
+            image::RgbImage::new(8, 8).save(input_dir.child(name).path()).unwrap();
+        }
+
+        let mut pipeline = Pipeline::from_config(&ProcessConfig::default());
+        pipeline.insert_before("compress", Invert).unwrap();
+
+        let processor = BatchProcessor::new(ProcessConfig::default(), 1)
+            .unwrap()
+            .with_pipeline(pipeline);
+        let stats = processor
+            .process_directory(input_dir.path(), output_dir.path(), false)
+            .unwrap();
+
+        assert_eq!(stats.processed_count, 2);
+        for entry in fs::read_dir(output_dir.path()).unwrap() {
+            let output = image::open(entry.unwrap().path()).unwrap();
+            assert_eq!(output.to_rgb8().get_pixel(3, 3).0, [255, 255, 255]);
+        }
+    }
+
+    fn encode_png(image: &DynamicImage) -> Vec<u8> {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut buffer, image::ImageFormat::Png).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn test_process_bytes_detects_format_from_content() {
+        let input = encode_png(&DynamicImage::new_rgb8(32, 16));
+        let config = ProcessConfig {
+            width: 8,
+            format: Some(pixie::OutputFormat::Jpeg),
+            ..Default::default()
+        };
+
+        let (output, stats) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+
+        assert_eq!(image::guess_format(&output).unwrap(), image::ImageFormat::Jpeg);
+        assert_eq!(image::load_from_memory(&output).unwrap().dimensions(), (8, 4));
+        assert_eq!(stats.total_size_before, input.len() as u64);
+        assert_eq!(stats.total_size_after, output.len() as u64);
+    }
+
+    #[test]
+    fn test_process_to_writer_keeps_input_format() {
+        let input = encode_png(&DynamicImage::new_rgb8(4, 4));
+        let mut output = Vec::new();
+
+        ImageProcessor::new(ProcessConfig::default())
+            .process_to_writer(input.as_slice(), &mut output)
+            .unwrap();
+
+        assert_eq!(image::guess_format(&output).unwrap(), image::ImageFormat::Png);
+    }
+
+    #[test]
+    fn test_process_reader_enforces_size_limit() {
+        let input = encode_png(&DynamicImage::new_rgb8(64, 64));
+        let config = ProcessConfig {
+            max_file_size: Some(16),
+            ..Default::default()
+        };
+
+        let result = ImageProcessor::new(config).process_reader(input.as_slice());
+
+        assert!(matches!(result, Err(pixie::ImageToolError::MemoryLimitExceeded(_))));
+    }
+
+    #[test]
+    fn test_stdin_input_defaults_to_stdout() {
+        let path = pixie::generate_output_path(std::path::Path::new("-"), None, "resized");
+        assert!(pixie::is_stdio_path(&path));
+
+        let path = pixie::generate_output_path(std::path::Path::new("photo.jpg"), None, "resized");
+        assert!(!pixie::is_stdio_path(&path));
+    }
+
+    /// Minimal little-endian EXIF block holding only an Orientation tag.
+    fn exif_with_orientation(orientation: u16) -> Vec<u8> {
+        let mut exif = b"II*\0".to_vec();
+        exif.extend_from_slice(&8u32.to_le_bytes());
+        exif.extend_from_slice(&1u16.to_le_bytes());
+        exif.extend_from_slice(&0x0112u16.to_le_bytes());
+        exif.extend_from_slice(&3u16.to_le_bytes());
+        exif.extend_from_slice(&1u32.to_le_bytes());
+        exif.extend_from_slice(&orientation.to_le_bytes());
+        exif.extend_from_slice(&[0, 0]);
+        exif.extend_from_slice(&0u32.to_le_bytes());
+        exif
+    }
+
+    fn encode_png_with_exif(image: &DynamicImage, exif: Vec<u8>) -> Vec<u8> {
+        use image::ImageEncoder;
+
+        let mut buffer = Vec::new();
+        let mut encoder = image::codecs::png::PngEncoder::new(&mut buffer);
+        encoder.set_exif_metadata(exif).unwrap();
+        let rgb = image.to_rgb8();
+        encoder
+            .write_image(&rgb, rgb.width(), rgb.height(), image::ExtendedColorType::Rgb8)
+            .unwrap();
+        buffer
+    }
+
+    #[test]
+    fn test_auto_orient_applies_exif_rotation() {
+        let mut source = image::RgbImage::new(4, 2);
+        source.put_pixel(0, 0, image::Rgb([255, 0, 0]));
+        let input = encode_png_with_exif(&DynamicImage::ImageRgb8(source), exif_with_orientation(6));
+
+        let (output, _) = ImageProcessor::new(ProcessConfig::default())
+            .process_bytes(&input)
+            .unwrap();
+        let output = image::load_from_memory(&output).unwrap().to_rgb8();
+
+        // Orientation 6 means "rotate 90° clockwise": the top-left pixel ends up top-right
+        assert_eq!(output.dimensions(), (2, 4));
+        assert_eq!(output.get_pixel(1, 0).0, [255, 0, 0]);
+
+        let config = ProcessConfig {
+            auto_orient: false,
+            ..Default::default()
+        };
+        let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+        assert_eq!(image::load_from_memory(&output).unwrap().dimensions(), (4, 2));
+    }
+
+    /// EXIF block with Orientation, Copyright, Artist, a GPS latitude reference and a 4x4 size.
+    fn tagged_exif() -> Vec<u8> {
+        use exif::{Field, In, Tag, Value};
+
+        let fields = [
+            Field { tag: Tag::Orientation, ifd_num: In::PRIMARY, value: Value::Short(vec![1]) },
+            Field { tag: Tag::Copyright, ifd_num: In::PRIMARY, value: Value::Ascii(vec![b"Jane Doe".to_vec()]) },
+            Field { tag: Tag::Artist, ifd_num: In::PRIMARY, value: Value::Ascii(vec![b"Jane".to_vec()]) },
+            Field { tag: Tag::GPSLatitudeRef, ifd_num: In::PRIMARY, value: Value::Ascii(vec![b"N".to_vec()]) },
+            Field { tag: Tag::PixelXDimension, ifd_num: In::PRIMARY, value: Value::Long(vec![4]) },
+            Field { tag: Tag::PixelYDimension, ifd_num: In::PRIMARY, value: Value::Long(vec![4]) },
+        ];
+        let mut writer = exif::experimental::Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        writer.write(&mut buffer, false).unwrap();
+        buffer.into_inner()
+    }
+
+    fn output_tags(output: &[u8]) -> Vec<String> {
+        let mut cursor = std::io::Cursor::new(output);
+        match exif::Reader::new().read_from_container(&mut cursor) {
+            Ok(exif) => exif.fields().map(|field| field.tag.to_string()).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_strip_metadata_removes_exif_from_every_format() {
+        let input = encode_png_with_exif(&DynamicImage::new_rgb8(4, 4), tagged_exif());
+
+        for format in [pixie::OutputFormat::Jpeg, pixie::OutputFormat::Png, pixie::OutputFormat::WebP] {
+            let config = ProcessConfig {
+                strip_metadata: true,
+                format: Some(format),
+                ..Default::default()
+            };
+            let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+
+            assert!(output_tags(&output).is_empty(), "{:?} output still has EXIF", format);
+            image::load_from_memory(&output).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_metadata_rules_keep_attribution_and_drop_gps() {
+        let input = encode_png_with_exif(&DynamicImage::new_rgb8(4, 4), tagged_exif());
+
+        let config = ProcessConfig {
+            strip_metadata: true,
+            metadata_rules: MetadataRules {
+                keep: vec!["copyright".to_string(), "Artist".to_string()],
+                drop: Vec::new(),
+            },
+            format: Some(pixie::OutputFormat::Jpeg),
+            ..Default::default()
+        };
+        let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+        let mut tags = output_tags(&output);
+        tags.sort();
+        assert_eq!(tags, vec!["Artist", "Copyright"]);
+
+        let config = ProcessConfig {
+            metadata_rules: MetadataRules {
+                keep: Vec::new(),
+                drop: vec!["gps".to_string()],
+            },
+            format: Some(pixie::OutputFormat::WebP),
+            ..Default::default()
+        };
+        let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+        let tags = output_tags(&output);
+        assert!(tags.contains(&"Copyright".to_string()));
+        assert!(!tags.iter().any(|tag| tag.starts_with("GPS")));
+    }
+
+
+    fn encode_jpeg_with_xmp(image: &DynamicImage, xmp: &[u8]) -> Vec<u8> {
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        image.write_to(&mut buffer, image::ImageFormat::Jpeg).unwrap();
+        let jpeg = buffer.into_inner();
+
+        let mut payload = b"http://ns.adobe.com/xap/1.0/\0".to_vec();
+        payload.extend_from_slice(xmp);
+        let mut output = jpeg[..2].to_vec();
+        output.extend_from_slice(&[0xFF, 0xE1]);
+        output.extend_from_slice(&((payload.len() + 2) as u16).to_be_bytes());
+        output.extend_from_slice(&payload);
+        output.extend_from_slice(&jpeg[2..]);
+        output
+    }
+
+    fn decoder_metadata(output: &[u8]) -> (Option<Vec<u8>>, Option<Vec<u8>>) {
+        use image::ImageDecoder;
+
+        let reader = image::ImageReader::new(std::io::Cursor::new(output))
+            .with_guessed_format()
+            .unwrap();
+        let mut decoder = reader.into_decoder().unwrap();
+        (decoder.exif_metadata().unwrap(), decoder.xmp_metadata().unwrap())
+    }
+
+    #[test]
+    fn test_exif_is_preserved_with_updated_dimensions() {
+        let input = encode_png_with_exif(&DynamicImage::new_rgb8(4, 4), tagged_exif());
+
+        for format in [pixie::OutputFormat::Jpeg, pixie::OutputFormat::Png, pixie::OutputFormat::WebP] {
+            let config = ProcessConfig {
+                width: 2,
+                format: Some(format),
+                ..Default::default()
+            };
+            let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+
+            let exif = exif::Reader::new()
+                .read_from_container(&mut std::io::Cursor::new(&output))
+                .unwrap();
+            let width = exif.get_field(exif::Tag::PixelXDimension, exif::In::PRIMARY).unwrap();
+            assert_eq!(width.value.get_uint(0), Some(2), "{:?}", format);
+            assert!(exif.get_field(exif::Tag::Copyright, exif::In::PRIMARY).is_some());
+        }
+    }
+
+    #[test]
+    fn test_xmp_is_transplanted_unless_stripped() {
+        let xmp = b"<x:xmpmeta xmlns:x=\"adobe:ns:meta/\"/>";
+        let input = encode_jpeg_with_xmp(&DynamicImage::new_rgb8(8, 8), xmp);
+
+        for format in [pixie::OutputFormat::Jpeg, pixie::OutputFormat::Png, pixie::OutputFormat::WebP] {
+            let config = ProcessConfig {
+                format: Some(format),
+                ..Default::default()
+            };
+            let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+            assert_eq!(decoder_metadata(&output).1.as_deref(), Some(&xmp[..]), "{:?}", format);
+
+            let config = ProcessConfig {
+                strip_metadata: true,
+                format: Some(format),
+                ..Default::default()
+            };
+            let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+            assert_eq!(decoder_metadata(&output), (None, None));
+        }
+    }
+
+
+    fn encode_png_with_icc(image: &DynamicImage, icc_profile: Vec<u8>) -> Vec<u8> {
+        use image::ImageEncoder;
+
+        let mut buffer = Vec::new();
+        let mut encoder = image::codecs::png::PngEncoder::new(&mut buffer);
+        encoder.set_icc_profile(icc_profile).unwrap();
+        let rgb = image.to_rgb8();
+        encoder
+            .write_image(&rgb, rgb.width(), rgb.height(), image::ExtendedColorType::Rgb8)
+            .unwrap();
+        buffer
+    }
+
+    fn decoder_icc_profile(output: &[u8]) -> Option<Vec<u8>> {
+        use image::ImageDecoder;
+
+        let reader = image::ImageReader::new(std::io::Cursor::new(output))
+            .with_guessed_format()
+            .unwrap();
+        reader.into_decoder().unwrap().icc_profile().unwrap()
+    }
+
+    #[test]
+    fn test_icc_profile_is_embedded_in_output() {
+        let p3 = moxcms::ColorProfile::new_display_p3().encode().unwrap();
+        let input = encode_png_with_icc(&DynamicImage::new_rgb8(4, 4), p3.clone());
+
+        for format in [pixie::OutputFormat::Jpeg, pixie::OutputFormat::Png, pixie::OutputFormat::WebP] {
+            let config = ProcessConfig {
+                format: Some(format),
+                ..Default::default()
+            };
+            let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+            assert_eq!(decoder_icc_profile(&output).as_deref(), Some(&p3[..]), "{:?}", format);
+        }
+
+        let config = ProcessConfig {
+            embed_icc_profile: false,
+            ..Default::default()
+        };
+        let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+        assert_eq!(decoder_icc_profile(&output), None);
+    }
+
+    #[test]
+    fn test_icc_profile_survives_metadata_rules() {
+        use image::ImageEncoder;
+
+        let p3 = moxcms::ColorProfile::new_display_p3().encode().unwrap();
+        let encode = |icc_profile: Vec<u8>| {
+            let mut input = Vec::new();
+            let mut encoder = image::codecs::png::PngEncoder::new(&mut input);
+            encoder.set_icc_profile(icc_profile).unwrap();
+            encoder.set_exif_metadata(tagged_exif()).unwrap();
+            encoder.write_image(&[0; 48], 4, 4, image::ExtendedColorType::Rgb8).unwrap();
+            input
+        };
+        let rules = MetadataRules {
+            drop: vec!["gps".to_string()],
+            ..Default::default()
+        };
+
+        for format in [pixie::OutputFormat::Jpeg, pixie::OutputFormat::Png, pixie::OutputFormat::WebP] {
+            let config = ProcessConfig {
+                metadata_rules: rules.clone(),
+                format: Some(format),
+                ..Default::default()
+            };
+            let (output, _) = ImageProcessor::new(config).process_bytes(&encode(p3.clone())).unwrap();
+            assert_eq!(decoder_icc_profile(&output).as_deref(), Some(&p3[..]), "{:?}", format);
+
+            let tags = output_tags(&output);
+            assert!(tags.contains(&"Copyright".to_string()), "{:?}", format);
+            assert!(!tags.contains(&"GPSLatitudeRef".to_string()), "{:?}", format);
+        }
+
+        // Profiles too large for one JPEG segment are split across several
+        let large: Vec<u8> = (0..150_000u32).map(|i| (i % 251) as u8).collect();
+        let config = ProcessConfig {
+            metadata_rules: rules,
+            format: Some(pixie::OutputFormat::Jpeg),
+            ..Default::default()
+        };
+        let (output, _) = ImageProcessor::new(config).process_bytes(&encode(large.clone())).unwrap();
+        assert_eq!(decoder_icc_profile(&output), Some(large));
+    }
+
+    #[test]
+    fn test_convert_to_srgb_rewrites_wide_gamut_pixels() {
+        let p3 = moxcms::ColorProfile::new_display_p3().encode().unwrap();
+        let source = image::RgbImage::from_pixel(4, 4, image::Rgb([200, 100, 50]));
+        let input = encode_png_with_icc(&DynamicImage::ImageRgb8(source), p3);
+
+        let config = ProcessConfig {
+            convert_to_srgb: true,
+            ..Default::default()
+        };
+        let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+
+        assert_eq!(decoder_icc_profile(&output), None);
+        let [r, g, b] = image::load_from_memory(&output).unwrap().to_rgb8().get_pixel(0, 0).0;
+        // P3 reds and greens are more saturated than sRGB can express at the same values
+        assert!(r > 200 && g < 100 && b <= 50, "unexpected sRGB pixel {:?}", [r, g, b]);
+    }
+
+
+    /// Noise compresses poorly, so quality has a visible effect on the output size.
+    fn noisy_image(width: u32, height: u32) -> DynamicImage {
+        let mut state = 0x2545_f491u32;
+        DynamicImage::ImageRgb8(image::RgbImage::from_fn(width, height, |_, _| {
+            let mut next = || {
+                state ^= state << 13;
+                state ^= state >> 17;
+                state ^= state << 5;
+                state as u8
+            };
+            image::Rgb([next(), next(), next()])
+        }))
+    }
+
+    #[test]
+    fn test_target_size_lowers_quality_to_fit() {
+        let input = encode_png(&noisy_image(64, 64));
+        let config = ProcessConfig {
+            format: Some(pixie::OutputFormat::Jpeg),
+            quality: 95,
+            target_size: Some(6 * 1024),
+            ..Default::default()
+        };
+
+        let (output, stats) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+
+        assert!(output.len() <= 6 * 1024);
+        assert_eq!(stats.chosen_qualities.len(), 1);
+        assert!(stats.chosen_qualities[0].1 < 95);
+        assert_eq!(image::load_from_memory(&output).unwrap().dimensions(), (64, 64));
+    }
+
+    #[test]
+    fn test_target_size_downscales_when_quality_is_not_enough() {
+        let input = encode_png(&noisy_image(64, 64));
+        let config = ProcessConfig {
+            format: Some(pixie::OutputFormat::Png),
+            target_size: Some(4 * 1024),
+            ..Default::default()
+        };
+        assert!(ImageProcessor::new(config.clone()).process_bytes(&input).is_err());
+
+        let config = ProcessConfig {
+            downscale_to_target: true,
+            ..config
+        };
+        let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+
+        assert!(output.len() <= 4 * 1024);
+        assert!(image::load_from_memory(&output).unwrap().width() < 64);
+    }
+
+    #[test]
+    fn test_batch_reports_chosen_quality_per_file() {
+        let input_dir = TempDir::new().unwrap();
+        let output_dir = TempDir::new().unwrap();
+        for name in ["a.png", "b.png"] {
+            noisy_image(48, 48).save(input_dir.child(name).path()).unwrap();
+        }
+
+        let config = ProcessConfig {
+            format: Some(pixie::OutputFormat::Jpeg),
+            target_size: Some(5 * 1024),
+            ..Default::default()
+        };
+        let stats = BatchProcessor::new(config, 1)
+            .unwrap()
+            .process_directory(input_dir.path(), output_dir.path(), false)
+            .unwrap();
+
+        assert_eq!(stats.processed_count, 2);
+        assert_eq!(stats.chosen_qualities.len(), 2);
+        for entry in fs::read_dir(output_dir.path()).unwrap() {
+            assert!(entry.unwrap().metadata().unwrap().len() <= 5 * 1024);
+        }
+    }
+
+    #[test]
+    fn test_parse_file_size() {
+        assert_eq!(pixie::parse_file_size("500").unwrap(), 500);
+        assert_eq!(pixie::parse_file_size("200KB").unwrap(), 200 * 1024);
+        assert_eq!(pixie::parse_file_size("1.5m").unwrap(), 1536 * 1024);
+        assert!(pixie::parse_file_size("lots").is_err());
+        assert!(pixie::parse_file_size("0").is_err());
+    }
+
+
+    #[test]
+    fn test_ssim_scores_identical_images_as_one() {
+        let image = noisy_image(32, 32);
+        assert!((pixie::ssim(&image, &image).unwrap() - 1.0).abs() < 1e-9);
+
+        let mut inverted = image.clone();
+        inverted.invert();
+        assert!(pixie::ssim(&image, &inverted).unwrap() < 0.5);
+        assert!(pixie::ssim(&image, &noisy_image(16, 16)).is_err());
+    }
+
+    #[test]
+    fn test_target_ssim_picks_lowest_passing_quality() {
+        let input = encode_png(&noisy_image(48, 48));
+        let process = |target: f64| {
+            let config = ProcessConfig {
+                format: Some(pixie::OutputFormat::Jpeg),
+                target_ssim: Some(target),
+                ..Default::default()
+            };
+            ImageProcessor::new(config).process_bytes(&input).unwrap().1
+        };
+
+        let loose = process(0.5);
+        let strict = process(0.95);
+
+        let (_, loose_quality) = loose.chosen_qualities[0];
+        let (_, strict_quality) = strict.chosen_qualities[0];
+        assert!(loose_quality < strict_quality);
+        assert!(strict.quality_scores[0].1 >= 0.95);
+        assert!(loose.total_size_after < strict.total_size_after);
+    }
+
+
+    /// 40x20 image whose left half is red and right half is blue.
+    fn split_image() -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_fn(40, 20, |x, _| {
+            if x < 20 { image::Rgb([255, 0, 0]) } else { image::Rgb([0, 0, 255]) }
+        }))
+    }
+
+    #[test]
+    fn test_cover_fit_crops_to_exact_size_with_gravity() {
+        let resizer = Resizer::new(ResizeAlgorithm::Nearest, true);
+
+        let west = resizer.resize(&split_image(), ResizeMode::Cover(10, 10, Gravity::West));
+        assert_eq!(west.dimensions(), (10, 10));
+        assert_eq!(west.to_rgb8().get_pixel(9, 5).0, [255, 0, 0]);
+
+        let east = resizer.resize(&split_image(), ResizeMode::Cover(10, 10, Gravity::East));
+        assert_eq!(east.to_rgb8().get_pixel(0, 5).0, [0, 0, 255]);
+
+        // A focal point near the right edge keeps the blue half
+        let focus = resizer.resize(&split_image(), ResizeMode::Cover(10, 10, Gravity::Focus(0.9, 0.5)));
+        assert_eq!(focus.to_rgb8().get_pixel(0, 5).0, [0, 0, 255]);
+    }
+
+    #[test]
+    fn test_cover_fit_from_config() {
+        let input = encode_png(&split_image());
+        let config = ProcessConfig {
+            width: 16,
+            height: 16,
+            fit: Fit::Cover,
+            gravity: Gravity::West,
+            algorithm: ResizeAlgorithm::Nearest,
+            ..Default::default()
+        };
+
+        let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+        let output = image::load_from_memory(&output).unwrap().to_rgb8();
+        assert_eq!(output.dimensions(), (16, 16));
+        assert_eq!(output.get_pixel(15, 8).0, [255, 0, 0]);
+
+        let invalid = ProcessConfig {
+            width: 16,
+            fit: Fit::Cover,
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+
+    #[test]
+    fn test_pad_fit_letterboxes_on_background() {
+        let resizer = Resizer::new(ResizeAlgorithm::Nearest, true);
+        let red = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(40, 20, image::Rgb([255, 0, 0])));
+
+        let padded = resizer.resize(
+            &red,
+            ResizeMode::Pad(20, 20, Gravity::Center, "#00ff00".parse().unwrap()),
+        );
+        let padded = padded.as_rgb8().expect("opaque padding keeps RGB");
+        assert_eq!(padded.dimensions(), (20, 20));
+        assert_eq!(padded.get_pixel(10, 0).0, [0, 255, 0]);
+        assert_eq!(padded.get_pixel(10, 10).0, [255, 0, 0]);
+
+        let north = resizer.resize(&red, ResizeMode::Pad(20, 20, Gravity::North, Background::Transparent));
+        let north = north.to_rgba8();
+        assert_eq!(north.get_pixel(10, 0).0, [255, 0, 0, 255]);
+        assert_eq!(north.get_pixel(10, 19).0[3], 0);
+
+        let blurred = resizer.resize(&red, ResizeMode::Pad(20, 20, Gravity::Center, Background::Blur));
+        assert_eq!(blurred.to_rgb8().get_pixel(10, 0).0, [255, 0, 0]);
+
+        // Padding keeps the bit depth of 16-bit and float inputs
+        let deep = DynamicImage::ImageRgb16(image::ImageBuffer::from_pixel(40, 20, image::Rgb([40000u16, 1, 0])));
+        let padded = resizer.resize(&deep, ResizeMode::Pad(20, 20, Gravity::Center, "#000".parse().unwrap()));
+        assert_eq!(padded.as_rgb16().expect("16-bit padding").get_pixel(10, 10).0, [40000, 1, 0]);
+        let transparent = resizer.resize(&deep, ResizeMode::Pad(20, 20, Gravity::Center, Background::Transparent));
+        assert_eq!(transparent.color(), image::ColorType::Rgba16);
+        let float = DynamicImage::ImageRgb32F(deep.to_rgb32f());
+        let padded = resizer.resize(&float, ResizeMode::Pad(20, 20, Gravity::Center, Background::Blur));
+        assert_eq!(padded.color(), image::ColorType::Rgb32F);
+    }
+
+    #[test]
+    fn test_background_parsing() {
+        assert_eq!("#fff".parse::<Background>().unwrap(), Background::Color([255, 255, 255, 255]));
+        assert_eq!("#11223380".parse::<Background>().unwrap(), Background::Color([0x11, 0x22, 0x33, 0x80]));
+        assert_eq!("Transparent".parse::<Background>().unwrap(), Background::Transparent);
+        assert!("#12345".parse::<Background>().is_err());
+        // Multibyte characters must be rejected, not sliced through
+        for value in ["€", "#€", "ab€d", "é123456"] {
+            assert!(value.parse::<Background>().is_err(), "{}", value);
+        }
+    }
+
+
+    #[test]
+    fn test_shrink_only_policy_leaves_small_images_alone() {
+        let resizer = Resizer::new(ResizeAlgorithm::Nearest, true).with_policy(ResizePolicy::ShrinkOnly);
+        let small = DynamicImage::new_rgb8(30, 20);
+        assert!(resizer.skip_reason(&small, ResizeMode::Width(80)).is_some());
+        assert_eq!(resizer.resize(&small, ResizeMode::Width(80)).dimensions(), (30, 20));
+        assert_eq!(resizer.resize(&small, ResizeMode::Width(15)).dimensions(), (15, 10));
+
+        let resizer = Resizer::new(ResizeAlgorithm::Nearest, true).with_policy(ResizePolicy::EnlargeOnly);
+        assert_eq!(resizer.resize(&small, ResizeMode::Width(15)).dimensions(), (30, 20));
+        assert_eq!(resizer.resize(&small, ResizeMode::Width(60)).dimensions(), (60, 40));
+    }
+
+    #[test]
+    fn test_resize_policy_keeps_pad_and_cover_canvas() {
+        let red = image::Rgb([255, 0, 0]);
+        let small = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(30, 20, red));
+        let white = Background::Color([255, 255, 255, 255]);
+
+        // A small image is padded at its own size instead of being enlarged
+        let shrink_only = Resizer::new(ResizeAlgorithm::Nearest, true).with_policy(ResizePolicy::ShrinkOnly);
+        let mode = ResizeMode::Pad(80, 60, Gravity::Center, white);
+        assert!(shrink_only.skip_reason(&small, mode).is_none());
+        let padded = shrink_only.resize(&small, mode).to_rgb8();
+        assert_eq!(padded.dimensions(), (80, 60));
+        assert_eq!(padded.get_pixel(40, 30).0, [255, 0, 0]);
+        assert_eq!(padded.get_pixel(24, 30).0, [255, 255, 255]);
+        assert_eq!(padded.get_pixel(25, 30).0, [255, 0, 0]);
+
+        // Cover keeps the target aspect without enlarging
+        let covered = shrink_only.resize(&small, ResizeMode::Cover(80, 40, Gravity::Center));
+        assert_eq!(covered.dimensions(), (30, 15));
+
+        // Enlarge-only crops a large image at its own scale
+        let large = DynamicImage::new_rgb8(60, 60);
+        let enlarge_only = Resizer::new(ResizeAlgorithm::Nearest, true).with_policy(ResizePolicy::EnlargeOnly);
+        let covered = enlarge_only.resize(&large, ResizeMode::Cover(20, 10, Gravity::Center));
+        assert_eq!(covered.dimensions(), (20, 10));
+        let padded = enlarge_only.resize(&large, ResizeMode::Pad(40, 20, Gravity::Center, white));
+        assert_eq!(padded.dimensions(), (40, 20));
+        assert_eq!(padded.to_rgb8().get_pixel(0, 0).0, [0, 0, 0]);
+    }
+
+    #[test]
+    fn test_batch_records_skipped_resizes() {
+        let input_dir = TempDir::new().unwrap();
+        let output_dir = TempDir::new().unwrap();
+        image::RgbImage::new(16, 16).save(input_dir.child("icon.png").path()).unwrap();
+        image::RgbImage::new(64, 64).save(input_dir.child("photo.png").path()).unwrap();
+
+        let config = ProcessConfig {
+            width: 32,
+            resize_policy: ResizePolicy::ShrinkOnly,
+            ..Default::default()
+        };
+        let stats = BatchProcessor::new(config, 1)
+            .unwrap()
+            .process_directory(input_dir.path(), output_dir.path(), false)
+            .unwrap();
+
+        assert_eq!(stats.skipped_resizes.len(), 1);
+        assert!(stats.skipped_resizes[0].0.ends_with("icon.png"));
+        assert!(stats.skipped_resizes[0].1.contains("shrink-only"));
+        assert_eq!(image::open(output_dir.child("icon.png").path()).unwrap().width(), 16);
+        assert_eq!(image::open(output_dir.child("photo.png").path()).unwrap().width(), 32);
+    }
+
+
+    #[test]
+    fn test_edge_and_pixel_constraints() {
+        let resizer = Resizer::new(ResizeAlgorithm::Nearest, false);
+        let image = DynamicImage::new_rgb8(400, 200);
+
+        assert_eq!(resizer.resize(&image, ResizeMode::LongestEdge(100)).dimensions(), (100, 50));
+        assert_eq!(resizer.resize(&image, ResizeMode::ShortestEdge(100)).dimensions(), (200, 100));
+
+        let capped = resizer.resize(&image, ResizeMode::MaxPixels(20_000));
+        assert!(capped.width() as u64 * capped.height() as u64 <= 20_000);
+        assert_eq!(capped.dimensions(), (200, 100));
+        assert_eq!(resizer.resize(&image, ResizeMode::MaxPixels(1_000_000)).dimensions(), (400, 200));
+    }
+
+    #[test]
+    fn test_mode_from_config_and_validation() {
+        let config = ProcessConfig {
+            longest_edge: 2048,
+            ..Default::default()
+        };
+        assert!(matches!(Resizer::calculate_mode_from_config(&config), ResizeMode::LongestEdge(2048)));
+        assert!(config.validate().is_ok());
+
+        let config = ProcessConfig {
+            max_pixels: 12_000_000,
+            ..Default::default()
+        };
+        assert!(matches!(Resizer::calculate_mode_from_config(&config), ResizeMode::MaxPixels(12_000_000)));
+
+        let conflicting = ProcessConfig {
+            width: 800,
+            shortest_edge: 300,
+            ..Default::default()
+        };
+        assert!(pixie::validate_config(&conflicting).is_err());
+    }
+
+    fn stripes_image() -> DynamicImage {
+        DynamicImage::ImageRgb8(image::RgbImage::from_fn(32, 8, |x, _| {
+            if x % 2 == 0 { image::Rgb([0, 0, 0]) } else { image::Rgb([255, 255, 255]) }
+        }))
+    }
+
+    #[test]
+    fn test_linear_light_resampling_keeps_brightness() {
+        let srgb = Resizer::new(ResizeAlgorithm::Bilinear, false)
+            .resize(&stripes_image(), ResizeMode::Absolute(16, 4));
+        let linear = Resizer::new(ResizeAlgorithm::Bilinear, false)
+            .with_linear_light(true)
+            .resize(&stripes_image(), ResizeMode::Absolute(16, 4));
+
+        // Averaging black and white in linear light is ~188 in sRGB, not 128
+        let grey = |image: &DynamicImage| image.to_rgb8().get_pixel(8, 2).0[0] as i32;
+        assert!((grey(&srgb) - 128).abs() <= 2, "sRGB average was {}", grey(&srgb));
+        assert!((grey(&linear) - 188).abs() <= 2, "linear average was {}", grey(&linear));
+
+        // Lanczos3 filters in linear light by default and keeps 16-bit input 16-bit
+        let deep = DynamicImage::ImageRgb16(stripes_image().to_rgb16());
+        let resized = Resizer::new(ResizeAlgorithm::Lanczos3, false).resize(&deep, ResizeMode::Absolute(16, 4));
+        assert_eq!(resized.color(), image::ColorType::Rgb16);
+        assert!(grey(&resized) > 170, "lanczos average was {}", grey(&resized));
+    }
+
+    #[test]
+    fn test_resize_premultiplies_alpha() {
+        // Transparent red columns next to opaque white ones
+        let rgba = DynamicImage::ImageRgba8(image::RgbaImage::from_fn(32, 8, |x, _| {
+            if x % 2 == 0 { image::Rgba([255, 0, 0, 0]) } else { image::Rgba([255, 255, 255, 255]) }
+        }));
+        let luma_alpha = DynamicImage::ImageLumaA8(image::GrayAlphaImage::from_fn(32, 8, |x, _| {
+            if x % 2 == 0 { image::LumaA([0, 0]) } else { image::LumaA([255, 255]) }
+        }));
+
+        for algorithm in [ResizeAlgorithm::Bilinear, ResizeAlgorithm::Bicubic, ResizeAlgorithm::Lanczos3] {
+            let resizer = Resizer::new(algorithm, false);
+
+            let resized = resizer.resize(&rgba, ResizeMode::Absolute(16, 4));
+            assert_eq!(resized.color(), image::ColorType::Rgba8);
+            let pixel = resized.to_rgba8().get_pixel(8, 2).0;
+            assert!(pixel[1] >= 250 && pixel[2] >= 250, "{:?} fringed to {:?}", algorithm, pixel);
+            assert!((pixel[3] as i32 - 128).abs() <= 8, "{:?} alpha was {}", algorithm, pixel[3]);
+
+            let resized = resizer.resize(&luma_alpha, ResizeMode::Absolute(16, 4));
+            assert_eq!(resized.color(), image::ColorType::La8);
+            let pixel = resized.to_luma_alpha8().get_pixel(8, 2).0;
+            assert!(pixel[0] >= 250, "{:?} darkened to {:?}", algorithm, pixel);
+        }
+    }
+
+    #[test]
+    fn test_convolution_resizer_matches_image_filters() {
+        use image::imageops::FilterType;
+
+        let source = noisy_image(67, 41);
+        let cases = [
+            (ResizeAlgorithm::Bilinear, FilterType::Triangle, ResampleFilter::triangle()),
+            (ResizeAlgorithm::Bicubic, FilterType::CatmullRom, ResampleFilter::catmull_rom()),
+            (ResizeAlgorithm::Lanczos3, FilterType::Lanczos3, ResampleFilter::lanczos3()),
+            (ResizeAlgorithm::Gaussian, FilterType::Gaussian, ResampleFilter::gaussian()),
+        ];
+
+        for (algorithm, filter_type, filter) in cases {
+            for (width, height) in [(20, 13), (150, 90), (67, 20)] {
+                let expected = source.resize_exact(width, height, filter_type).to_rgb8();
+                let resized = Resizer::new(algorithm, false)
+                    .with_linear_light(false)
+                    .resize(&source, ResizeMode::Absolute(width, height))
+                    .to_rgb8();
+                let max_diff = expected
+                    .as_raw()
+                    .iter()
+                    .zip(resized.as_raw())
+                    .map(|(&a, &b)| (a as i32 - b as i32).abs())
+                    .max()
+                    .unwrap();
+                assert!(max_diff <= 1, "{:?} {}x{} differs by {}", algorithm, width, height, max_diff);
+            }
+
+            // The SIMD and portable loops agree on one, three and four channels
+            let engine = ConvolutionResizer::new(filter);
+            let pairs = [
+                (
+                    engine.resize(&source.to_luma32f(), 30, 70).into_raw(),
+                    engine.without_simd().resize(&source.to_luma32f(), 30, 70).into_raw(),
+                ),
+                (
+                    engine.resize(&source.to_rgb32f(), 30, 70).into_raw(),
+                    engine.without_simd().resize(&source.to_rgb32f(), 30, 70).into_raw(),
+                ),
+                (
+                    engine.resize(&source.to_rgba32f(), 30, 70).into_raw(),
+                    engine.without_simd().resize(&source.to_rgba32f(), 30, 70).into_raw(),
+                ),
+            ];
+            for (simd, scalar) in pairs {
+                for (a, b) in simd.iter().zip(&scalar) {
+                    assert!((a - b).abs() < 1e-5, "{:?}: SIMD {} vs scalar {}", algorithm, a, b);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_additional_resampling_filters() {
+        let flat = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(45, 30, image::Rgb([200, 100, 50])));
+        let algorithms = [
+            ResizeAlgorithm::Lanczos2,
+            ResizeAlgorithm::Mitchell,
+            ResizeAlgorithm::Gaussian,
+            ResizeAlgorithm::Box,
+            ResizeAlgorithm::Hermite,
+        ];
+
+        for algorithm in algorithms {
+            let resizer = Resizer::new(algorithm, false);
+            for (width, height) in [(20, 13), (90, 61)] {
+                let resized = resizer.resize(&flat, ResizeMode::Absolute(width, height));
+                assert_eq!(resized.dimensions(), (width, height));
+                for pixel in resized.to_rgb8().pixels() {
+                    assert_eq!(pixel.0, [200, 100, 50], "{:?} changed a flat image", algorithm);
+                }
+            }
+        }
+
+        // Box averages exactly the source pixels under each output pixel
+        let blocks = DynamicImage::ImageLuma8(image::GrayImage::from_fn(4, 2, |x, _| image::Luma([(x * 60) as u8])));
+        let averaged = Resizer::new(ResizeAlgorithm::Box, false).resize(&blocks, ResizeMode::Absolute(2, 1));
+        assert_eq!(averaged.to_luma8().as_raw(), &vec![30, 150]);
+    }
+
+    #[test]
+    fn test_unsharp_mask_and_auto_sharpening() {
+        assert_eq!("auto".parse::<Sharpen>().unwrap(), Sharpen::Auto);
+        assert_eq!(
+            "0.8, 0.6, 2".parse::<Sharpen>().unwrap(),
+            Sharpen::Mask(UnsharpMask { radius: 0.8, amount: 0.6, threshold: 2 })
+        );
+        assert!("0.8".parse::<Sharpen>().is_err());
+
+        // A soft edge gets darker on its dark side and brighter on its light side
+        let ramp = DynamicImage::ImageLuma8(image::GrayImage::from_fn(16, 4, |x, _| {
+            image::Luma([[60, 60, 60, 60, 60, 60, 90, 130, 170, 200, 200, 200, 200, 200, 200, 200][x as usize]])
+        }));
+        let mask = UnsharpMask { radius: 1.0, amount: 1.0, threshold: 0 };
+        let sharpened = Sharpener::unsharp_mask(&ramp, mask);
+        assert_eq!(sharpened.color(), image::ColorType::L8);
+        let sharpened = sharpened.to_luma8();
+        assert!(sharpened.get_pixel(5, 2).0[0] < 60);
+        assert!(sharpened.get_pixel(10, 2).0[0] > 200);
+        assert_eq!(sharpened.get_pixel(0, 2).0[0], 60);
+
+        // A high threshold leaves the gentle ramp alone
+        let untouched = Sharpener::unsharp_mask(&ramp, UnsharpMask { threshold: 100, ..mask });
+        assert_eq!(untouched.to_luma8(), ramp.to_luma8());
+
+        // Auto strength grows with the reduction and is off when enlarging
+        assert!(Sharpener::auto_mask(1.0).is_none());
+        assert!(Sharpener::auto_mask(2.0).unwrap().amount < Sharpener::auto_mask(4.0).unwrap().amount);
+
+        let config = ProcessConfig {
+            width: 20,
+            sharpen: Some(Sharpen::Auto),
+            ..Default::default()
+        };
+        let names = Pipeline::from_config(&config).operation_names().join(",");
+        assert!(names.contains("resize,sharpen,compress"), "{}", names);
+        let (output, _) = ImageProcessor::new(config).process_bytes(&encode_png(&noisy_image(80, 40))).unwrap();
+        assert_eq!(image::load_from_memory(&output).unwrap().dimensions(), (20, 10));
+    }
+
+    #[test]
+    fn test_color_adjustments() {
+        let adjust = |adjustments: Adjustments, rgb: [u8; 3]| {
+            let image = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(2, 2, image::Rgb(rgb)));
+            let adjusted = Adjuster::new(adjustments).apply(&image);
+            assert_eq!(adjusted.color(), image::ColorType::Rgb8);
+            adjusted.to_rgb8().get_pixel(0, 0).0
+        };
+        let none = Adjustments::default();
+
+        assert_eq!(adjust(none, [12, 34, 56]), [12, 34, 56]);
+        assert_eq!(adjust(Adjustments { brightness: 0.2, ..none }, [100, 100, 100]), [151, 151, 151]);
+        assert_eq!(adjust(Adjustments { contrast: -1.0, ..none }, [10, 200, 90]), [128, 128, 128]);
+        assert_eq!(adjust(Adjustments { gamma: 2.0, ..none }, [64, 64, 64]), [128, 128, 128]);
+
+        let grey = adjust(Adjustments { saturation: -1.0, ..none }, [200, 40, 40]);
+        assert!(grey[0].abs_diff(grey[1]) <= 1 && grey[1].abs_diff(grey[2]) <= 1, "{:?}", grey);
+
+        let rotated = adjust(Adjustments { hue: 120.0, ..none }, [200, 40, 40]);
+        assert!(rotated[1] > rotated[0] && rotated[1] > rotated[2], "{:?}", rotated);
+
+        // Identity adjustments stay out of the pipeline, invalid ones are rejected
+        let names = Pipeline::from_config(&ProcessConfig::default()).operation_names().join(",");
+        assert!(!names.contains("adjust"));
+        let config = ProcessConfig {
+            adjustments: Adjustments { saturation: 0.5, ..none },
+            ..Default::default()
+        };
+        assert!(Pipeline::from_config(&config).operation_names().contains(&"adjust"));
+        let invalid = ProcessConfig {
+            adjustments: Adjustments { brightness: 2.0, ..none },
+            ..Default::default()
+        };
+        assert!(invalid.validate().is_err());
+    }
+
+    #[test]
+    fn test_rotations_and_flips() {
+        let image = split_image();
+        let pixel = |image: &DynamicImage, x, y| image.to_rgb8().get_pixel(x, y).0;
+        let red = [255, 0, 0];
+        let blue = [0, 0, 255];
+
+        let rotated = Transformer::apply(&image, Transform::Rotate90);
+        assert_eq!(rotated.dimensions(), (20, 40));
+        assert_eq!(pixel(&rotated, 10, 0), red);
+        assert_eq!(pixel(&rotated, 10, 39), blue);
+
+        let flipped = Transformer::apply(&image, Transform::FlipHorizontal);
+        assert_eq!(pixel(&flipped, 0, 0), blue);
+        let transposed = Transformer::apply(&image, Transform::Transpose);
+        assert_eq!((transposed.dimensions(), pixel(&transposed, 0, 0)), ((20, 40), red));
+        let transversed = Transformer::apply(&image, Transform::Transverse);
+        assert_eq!((transversed.dimensions(), pixel(&transversed, 0, 0)), ((20, 40), blue));
+
+        // Right angles given in degrees are exact
+        let quarter = Transformer::rotate(&image, -270.0, [255, 255, 255, 255], false);
+        assert_eq!(quarter.to_rgb8(), rotated.to_rgb8());
+
+        // Arbitrary angles grow the canvas and fill the corners
+        let filled = Transformer::rotate(&image, 30.0, [0, 255, 0, 255], false);
+        assert_eq!(filled.dimensions(), (45, 38));
+        assert_eq!(filled.color(), image::ColorType::Rgb8);
+        assert_eq!(pixel(&filled, 0, 0), [0, 255, 0]);
+
+        let transparent = Transformer::rotate(&image, 30.0, [0, 0, 0, 0], false);
+        assert_eq!(transparent.color(), image::ColorType::Rgba8);
+        assert_eq!(transparent.to_rgba8().get_pixel(0, 0).0[3], 0);
+
+        // Cropping leaves only image pixels
+        let cropped = Transformer::rotate(&image, 30.0, [0, 255, 0, 255], true);
+        let (width, height) = cropped.dimensions();
+        assert!(width < 40 && height < 20 && width > 10 && height > 5, "{}x{}", width, height);
+        for p in cropped.to_rgb8().pixels() {
+            assert!(p.0[1] < 40, "fill leaked into crop: {:?}", p.0);
+        }
+
+        let config = ProcessConfig {
+            transforms: vec![Transform::Rotate90, Transform::FlipVertical],
+            ..Default::default()
+        };
+        let (output, _) = ImageProcessor::new(config).process_bytes(&encode_png(&image)).unwrap();
+        let output = image::load_from_memory(&output).unwrap();
+        assert_eq!(output.dimensions(), (20, 40));
+        assert_eq!(pixel(&output, 10, 0), blue);
+    }
+
+    #[test]
+    fn test_watermark_placement_opacity_and_tiling() {
+        let black = DynamicImage::ImageRgb8(image::RgbImage::new(40, 20));
+        let logo = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(8, 4, image::Rgba([255, 255, 255, 255])));
+        let watermark = Watermark {
+            margin: 2,
+            scale: 0.0,
+            opacity: 1.0,
+            ..Watermark::new("unused.png")
+        };
+        let pixel = |image: &DynamicImage, x, y| image.to_rgb8().get_pixel(x, y).0[0];
+
+        let single = Watermarker::apply(&black, &logo, &watermark);
+        assert_eq!(single.color(), image::ColorType::Rgb8);
+        assert_eq!(pixel(&single, 37, 17), 255);
+        assert_eq!(pixel(&single, 30, 14), 255);
+        assert_eq!(pixel(&single, 38, 18), 0);
+        assert_eq!(pixel(&single, 29, 17), 0);
+
+        let faint = Watermarker::apply(&black, &logo, &Watermark { opacity: 0.5, ..watermark.clone() });
+        assert!((pixel(&faint, 35, 15) as i32 - 128).abs() <= 1);
+
+        let scaled = Watermarker::apply(
+            &black,
+            &logo,
+            &Watermark { scale: 0.5, gravity: Gravity::NorthWest, ..watermark.clone() },
+        );
+        assert_eq!(pixel(&scaled, 21, 11), 255);
+        assert_eq!(pixel(&scaled, 22, 2), 0);
+
+        let tiled = Watermarker::apply(
+            &black,
+            &logo,
+            &Watermark { layout: WatermarkLayout::Tile, ..watermark.clone() },
+        );
+        assert_eq!((pixel(&tiled, 0, 0), pixel(&tiled, 9, 0), pixel(&tiled, 12, 7)), (255, 0, 255));
+
+        let diagonal = Watermarker::apply(
+            &black,
+            &logo,
+            &Watermark { layout: WatermarkLayout::Diagonal, ..watermark.clone() },
+        );
+        assert!(diagonal.to_rgb8().pixels().any(|p| p.0[0] > 200));
+
+        // A one-pixel logo is not composited a million times
+        let dot = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(1, 1, image::Rgba([255, 255, 255, 255])));
+        let tiny = Watermarker::apply(
+            &DynamicImage::ImageRgb8(image::RgbImage::new(1000, 1000)),
+            &dot,
+            &Watermark { layout: WatermarkLayout::Tile, margin: 0, ..watermark.clone() },
+        );
+        let copies = tiny.to_rgb8().pixels().filter(|p| p.0[0] == 255).count();
+        assert!(copies > 1000 && copies <= 4096, "{} copies", copies);
+
+        // From the config, the logo is read from disk; a missing logo fails the image
+        let temp_dir = TempDir::new().unwrap();
+        let logo_path = temp_dir.child("logo.png");
+        logo.save(logo_path.path()).unwrap();
+        let config = ProcessConfig {
+            watermark: Some(Watermark { path: logo_path.path().to_path_buf(), ..watermark.clone() }),
+            ..Default::default()
+        };
+        let (output, _) = ImageProcessor::new(config).process_bytes(&encode_png(&black)).unwrap();
+        assert_eq!(pixel(&image::load_from_memory(&output).unwrap(), 37, 17), 255);
+
+        let missing = ProcessConfig {
+            watermark: Some(Watermark::new(temp_dir.child("missing.png").path())),
+            ..Default::default()
+        };
+        assert!(ImageProcessor::new(missing).process_bytes(&encode_png(&black)).is_err());
+    }
+
+
+    #[test]
+    fn test_text_overlay_with_stroke_and_shadow() {
+        let black = DynamicImage::ImageRgb8(image::RgbImage::new(200, 60));
+        let font = TextRenderer::bundled_font();
+        let overlay = TextOverlay {
+            size: 24.0,
+            gravity: Gravity::NorthWest,
+            margin: 4,
+            ..TextOverlay::new("Hi")
+        };
+        let bright = |image: &DynamicImage, x0: u32, x1: u32| {
+            image
+                .to_rgb8()
+                .enumerate_pixels()
+                .filter(|&(x, _, p)| (x0..x1).contains(&x) && p.0[0] > 200)
+                .count()
+        };
+
+        let plain = TextRenderer::apply(&black, &font, &overlay);
+        assert_eq!(plain.color(), image::ColorType::Rgb8);
+        assert!(bright(&plain, 4, 40) > 20);
+        assert_eq!(bright(&plain, 40, 200), 0);
+
+        // Lines are aligned to the gravity's side
+        let right = TextRenderer::apply(
+            &black,
+            &font,
+            &TextOverlay { gravity: Gravity::SouthEast, text: "Hi\nHello".into(), ..overlay.clone() },
+        );
+        assert_eq!(bright(&right, 0, 100), 0);
+        assert!(bright(&right, 180, 196) > 0);
+
+        let outlined = TextRenderer::apply(
+            &black,
+            &font,
+            &TextOverlay { stroke_width: 2, stroke_color: [255, 0, 0, 255], ..overlay.clone() },
+        );
+        let red = |image: &DynamicImage| {
+            image.to_rgb8().pixels().filter(|p| p.0[0] > 200 && p.0[1] < 50).count()
+        };
+        assert!(red(&outlined) > 20);
+        assert_eq!(red(&plain), 0);
+
+        let shadowed = TextRenderer::apply(
+            &black,
+            &font,
+            &TextOverlay { shadow: Some((6, 0)), shadow_color: [0, 0, 255, 255], ..overlay.clone() },
+        );
+        let blue = shadowed.to_rgb8().pixels().filter(|p| p.0[2] > 200 && p.0[0] < 50).count();
+        assert!(blue > 10);
+
+        // From the config the bundled font is used; a missing font file fails the image
+        let config = ProcessConfig { text_overlay: Some(overlay.clone()), ..Default::default() };
+        let (output, _) = ImageProcessor::new(config).process_bytes(&encode_png(&black)).unwrap();
+        assert!(bright(&image::load_from_memory(&output).unwrap(), 4, 40) > 20);
+
+        let missing = ProcessConfig {
+            text_overlay: Some(TextOverlay { font: Some("missing.ttf".into()), ..overlay.clone() }),
+            ..Default::default()
+        };
+        assert!(ImageProcessor::new(missing).process_bytes(&encode_png(&black)).is_err());
+
+        let empty = ProcessConfig { text_overlay: Some(TextOverlay::new(" ")), ..Default::default() };
+        assert!(empty.validate().is_err());
+    }
+
+
+    #[test]
+    fn test_redaction_regions_styles_and_sidecars() {
+        assert_eq!(
+            "10,20,30,40".parse::<Region>().unwrap(),
+            Region::Pixels { x: 10, y: 20, width: 30, height: 40 }
+        );
+        assert_eq!(
+            "50%,0,0.25,1".parse::<Region>().unwrap(),
+            Region::Relative { x: 0.5, y: 0.0, width: 0.25, height: 1.0 }
+        );
+        assert!("10,20,30".parse::<Region>().is_err());
+        let clipped = Region::Pixels { x: 30, y: 0, width: 20, height: 5 };
+        assert_eq!(clipped.to_pixels(40, 20), Some((30, 0, 10, 5)));
+        let quarter = Region::Relative { x: 0.5, y: 0.5, width: 0.5, height: 0.5 };
+        assert_eq!(quarter.to_pixels(40, 20), Some((20, 10, 20, 10)));
+        let outside = Region::Pixels { x: 40, y: 0, width: 5, height: 5 };
+        assert_eq!(outside.to_pixels(40, 20), None);
+
+        assert_eq!("blur".parse::<RedactStyle>().unwrap(), RedactStyle::Blur { sigma: None });
+        assert_eq!("pixelate:8".parse::<RedactStyle>().unwrap(), RedactStyle::Pixelate { block: Some(8) });
+        assert_eq!("fill:#ff0000".parse::<RedactStyle>().unwrap(), RedactStyle::Fill([255, 0, 0, 255]));
+        assert!("smudge".parse::<RedactStyle>().is_err());
+
+        // A checkerboard shows whether detail survives
+        let image = DynamicImage::ImageRgb8(image::RgbImage::from_fn(40, 20, |x, y| {
+            image::Rgb(if (x + y) % 2 == 0 { [255, 255, 255] } else { [0, 0, 0] })
+        }));
+        let region = [Region::Pixels { x: 8, y: 4, width: 16, height: 8 }];
+        let untouched = |redacted: &DynamicImage| {
+            let (before, after) = (image.to_rgb8(), redacted.to_rgb8());
+            before
+                .enumerate_pixels()
+                .filter(|&(x, y, _)| !(8..24).contains(&x) || !(4..12).contains(&y))
+                .all(|(x, y, p)| after.get_pixel(x, y) == p)
+        };
+
+        let filled = Redactor::new(RedactStyle::Fill([255, 0, 0, 255])).apply(&image, &region).unwrap();
+        assert_eq!(filled.color(), image::ColorType::Rgb8);
+        assert_eq!(filled.to_rgb8().get_pixel(8, 4).0, [255, 0, 0]);
+        assert_eq!(filled.to_rgb8().get_pixel(23, 11).0, [255, 0, 0]);
+        assert!(untouched(&filled));
+
+        let pixelated = Redactor::new(RedactStyle::Pixelate { block: Some(4) }).apply(&image, &region).unwrap();
+        assert!((pixelated.to_rgb8().get_pixel(9, 5).0[0] as i32 - 128).abs() <= 1);
+        assert_eq!(pixelated.to_rgb8().get_pixel(9, 5), pixelated.to_rgb8().get_pixel(11, 7));
+        assert!(untouched(&pixelated));
+        let single = Redactor::new(RedactStyle::Pixelate { block: Some(0) }).apply(&image, &region).unwrap();
+        assert_eq!(single.to_rgb8(), image.to_rgb8());
+
+        let blurred = Redactor::new(RedactStyle::Blur { sigma: Some(3.0) }).apply(&image, &region).unwrap();
+        let blurred_rgb = blurred.to_rgb8();
+        for (x, y) in [(10, 6), (15, 8), (20, 10)] {
+            assert!((blurred_rgb.get_pixel(x, y).0[0] as i32 - 128).abs() < 20);
+        }
+        assert!(untouched(&blurred));
+
+        // A region outside the image fails instead of leaving its area visible
+        let outside = [region[0], Region::Pixels { x: 50, y: 0, width: 5, height: 5 }];
+        assert!(Redactor::new(RedactStyle::Fill([0, 0, 0, 255])).apply(&image, &outside).is_err());
+
+        // Batch regions come from photo.png.json sidecars; files without one are left alone
+        let input_dir = TempDir::new().unwrap();
+        let output_dir = TempDir::new().unwrap();
+        image.save(input_dir.child("a.png").path()).unwrap();
+        image.save(input_dir.child("b.png").path()).unwrap();
+        input_dir
+            .child("a.png.json")
+            .write_str(r#"{"regions": [{"x": 0.5, "y": 0, "width": 0.5, "height": 1, "relative": true}]}"#)
+            .unwrap();
+        let config = ProcessConfig {
+            redaction: Some(Redaction {
+                regions: Vec::new(),
+                style: RedactStyle::Fill([0, 0, 255, 255]),
+                sidecars: true,
+            }),
+            ..Default::default()
+        };
+        let stats = BatchProcessor::new(config.clone(), 1)
+            .unwrap()
+            .process_directory(input_dir.path(), output_dir.path(), false)
+            .unwrap();
+        assert_eq!(stats.processed_count, 2);
+        let a = image::open(output_dir.child("a.png").path()).unwrap().to_rgb8();
+        let b = image::open(output_dir.child("b.png").path()).unwrap().to_rgb8();
+        assert_eq!(a.get_pixel(30, 10).0, [0, 0, 255]);
+        assert_eq!(a.get_pixel(10, 10).0, [255, 255, 255]);
+        assert_eq!(b, image.to_rgb8());
+
+        input_dir
+            .child("b.png.json")
+            .write_str(r#"{"regions": [{"x": 100, "y": 0, "width": 10, "height": 10}]}"#)
+            .unwrap();
+        let failed = BatchProcessor::new(config.clone(), 1)
+            .unwrap()
+            .process_directory(input_dir.path(), output_dir.path(), false)
+            .unwrap();
+        assert_eq!(failed.processed_count, 1);
+        assert_eq!(failed.errors.len(), 1);
+        assert!(failed.errors[0].1.contains("b.png: Redaction region"));
+
+        let negative = r#"{"regions": [{"x": -1, "y": 0, "width": 1, "height": 1}]}"#;
+        assert!(Redactor::parse_sidecar(negative).is_err());
+        assert!(Redactor::parse_sidecar(r#"{"areas": []}"#).is_err());
+        let nothing = ProcessConfig { redaction: Some(Redaction::default()), ..Default::default() };
+        assert!(nothing.validate().is_err());
+    }
+
+
+    #[test]
+    fn test_responsive_variants_html_and_manifest() {
+        let input_dir = TempDir::new().unwrap();
+        let output_dir = TempDir::new().unwrap();
+        let input = input_dir.child("photo.png");
+        noisy_image(200, 100).save(input.path()).unwrap();
+
+        let spec = ResponsiveSpec {
+            widths: vec![100, 50, 400],
+            formats: vec![pixie::OutputFormat::WebP, pixie::OutputFormat::Jpeg],
+            template: "{name}-{width}x{height}.{ext}".to_string(),
+        };
+        let set = ResponsiveGenerator::new(ProcessConfig::default(), spec)
+            .generate(input.path(), output_dir.path())
+            .unwrap();
+
+        // 400 is wider than the source and replaced by its width; widths come out ascending
+        assert_eq!((set.name.as_str(), set.width, set.height), ("photo", 200, 100));
+        let files: Vec<&str> = set.variants.iter().map(|v| v.file.as_str()).collect();
+        assert_eq!(
+            files,
+            [
+                "photo-50x25.webp", "photo-100x50.webp", "photo-200x100.webp",
+                "photo-50x25.jpg", "photo-100x50.jpg", "photo-200x100.jpg",
+            ]
+        );
+        for variant in &set.variants {
+            let written = image::open(output_dir.child(&variant.file).path()).unwrap();
+            assert_eq!(written.dimensions(), (variant.width, variant.height));
+            let bytes = fs::metadata(output_dir.child(&variant.file).path()).unwrap().len();
+            assert_eq!(variant.bytes, bytes);
+        }
+
+        let html = set.picture_html("/img/", "50vw", "A \"photo\"");
+        assert!(html.contains(
+            r#"srcset="/img/photo-50x25.webp 50w, /img/photo-100x50.webp 100w, /img/photo-200x100.webp 200w""#
+        ));
+        assert!(html.contains(r#"<img src="/img/photo-200x100.jpg""#));
+        assert!(html.contains(r#"width="200" height="100" alt="A &quot;photo&quot;""#));
+
+        let manifest = set.manifest().unwrap();
+        assert!(manifest.contains(r#""mime_type": "image/jpeg""#));
+        assert!(manifest.contains(r#""file": "photo-100x50.webp""#));
+
+        // A source narrower than every width is written at its own size
+        let small = ResponsiveGenerator::new(ProcessConfig::default(), ResponsiveSpec::default())
+            .generate(input.path(), output_dir.path())
+            .unwrap();
+        assert_eq!(small.variants.len(), 1);
+        assert_eq!(small.variants[0].file, "photo-200w.png");
+
+        let unnamed = ResponsiveSpec { template: "{name}.{ext}".to_string(), ..ResponsiveSpec::default() };
+        assert!(unnamed.validate().is_err());
+        let escaping =
+            ResponsiveSpec { template: "../{width}.{ext}".to_string(), ..ResponsiveSpec::default() };
+        assert!(escaping.validate().is_err());
+        let nested =
+            ResponsiveSpec { template: "{width}/{name}.{ext}".to_string(), ..ResponsiveSpec::default() };
+        assert!(nested.validate().is_err());
+        // Names supplied with an already decoded image are checked after expansion
+        assert!(ResponsiveSpec::default().file_name("../photo", 100, 50, "jpg").is_err());
+    }
+
+    /// EXIF block of a photo: orientation 6, capture date and exposure.
+    fn photo_exif() -> Vec<u8> {
+        use exif::{Field, In, Rational, Tag, Value};
+
+        let rational = |num, denom| Value::Rational(vec![Rational { num, denom }]);
+        let fields = [
+            Field { tag: Tag::Orientation, ifd_num: In::PRIMARY, value: Value::Short(vec![6]) },
+            Field { tag: Tag::DateTimeOriginal, ifd_num: In::PRIMARY, value: Value::Ascii(vec![b"2024:05:06 07:08:09".to_vec()]) },
+            Field { tag: Tag::ExposureTime, ifd_num: In::PRIMARY, value: rational(1, 250) },
+            Field { tag: Tag::FNumber, ifd_num: In::PRIMARY, value: rational(28, 10) },
+            Field { tag: Tag::PhotographicSensitivity, ifd_num: In::PRIMARY, value: Value::Short(vec![200]) },
+            Field { tag: Tag::FocalLength, ifd_num: In::PRIMARY, value: rational(50, 1) },
+        ];
+        let mut writer = exif::experimental::Writer::new();
+        for field in &fields {
+            writer.push_field(field);
+        }
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        writer.write(&mut buffer, false).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn test_contact_sheet_pages_captions_and_orientation() {
+        let temp_dir = TempDir::new().unwrap();
+        let input_dir = temp_dir.child("photos");
+        input_dir.create_dir_all().unwrap();
+
+        // a.png is 40x20 and rotated upright by its EXIF; the rest have no EXIF
+        let red = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(40, 20, image::Rgb([255, 0, 0])));
+        input_dir.child("a.png").write_binary(&encode_png_with_exif(&red, photo_exif())).unwrap();
+        for name in ["b", "c", "d", "e", "f", "g"] {
+            noisy_image(30, 30).save(input_dir.child(format!("{}.png", name)).path()).unwrap();
+        }
+        input_dir.child("zz.png").write_binary(b"not an image").unwrap();
+
+        let sheet = ContactSheet {
+            columns: 3,
+            rows: 2,
+            thumbnail_size: 40,
+            spacing: 10,
+            exif_captions: true,
+            caption_size: 10.0,
+            ..Default::default()
+        };
+        let result = ContactSheetMaker::new(sheet.clone(), 2)
+            .unwrap()
+            .render_directory(input_dir.path(), false)
+            .unwrap();
+
+        assert_eq!(result.pages.len(), 2);
+        assert_eq!(result.images, 7);
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].0.ends_with("zz.png"));
+
+        let (first, last) = (result.pages[0].to_rgb8(), result.pages[1].to_rgb8());
+        assert_eq!(result.pages[0].color(), image::ColorType::Rgb8);
+        assert_eq!(first.width(), 10 + 3 * (40 + 10));
+        assert_eq!(last.width(), first.width());
+        // Two rows on the first page, one on the last
+        let row_end = last.height() - 10;
+        assert_eq!(first.height() - last.height(), row_end);
+        assert!(row_end > 10 + 40 + 3 * 10, "three caption lines fit under each thumbnail");
+
+        // The 20x40 upright thumbnail is centered in the first 40x40 cell
+        assert_eq!(first.get_pixel(30, 30).0, [255, 0, 0]);
+        assert_eq!(first.get_pixel(15, 30).0, [255, 255, 255]);
+
+        // Date and exposure lines are drawn under a.png only; b.png has no EXIF
+        let inked = |x0: u32, y0: u32, y1: u32| {
+            (y0..y1).any(|y| (x0..x0 + 40).any(|x| first.get_pixel(x, y).0 != [255, 255, 255]))
+        };
+        let exif_lines = 10 + 40 + 5 + 14;
+        assert!(inked(10, 50, exif_lines), "file name caption");
+        assert!(inked(60, 50, exif_lines), "file name caption");
+        assert!(inked(10, exif_lines, row_end));
+        assert!(!inked(60, exif_lines, row_end));
+
+        // Without captions the rows are just the thumbnails
+        let plain = ContactSheet {
+            filename_captions: false,
+            exif_captions: false,
+            rows: 0,
+            background: [0, 0, 0, 0],
+            ..sheet
+        };
+        let result = ContactSheetMaker::new(plain, 0)
+            .unwrap()
+            .render_directory(input_dir.path(), false)
+            .unwrap();
+        assert_eq!(result.pages.len(), 1);
+        assert_eq!(result.pages[0].color(), image::ColorType::Rgba8);
+        assert_eq!(result.pages[0].height(), 10 + 3 * (40 + 10));
+
+        // Oversized pages are refused before anything is decoded
+        assert!(ContactSheetMaker::new(ContactSheet { spacing: u32::MAX, ..Default::default() }, 0).is_err());
+        let huge = ContactSheet { columns: 100, rows: 0, thumbnail_size: 4096, ..Default::default() };
+        assert!(ContactSheetMaker::new(huge, 0).unwrap().render_directory(input_dir.path(), false).is_err());
+
+        let output = std::path::Path::new("out/sheet.jpg");
+        assert_eq!(ContactSheetMaker::page_path(output, 1, 1), output);
+        assert_eq!(ContactSheetMaker::page_path(output, 2, 3), std::path::Path::new("out/sheet-2.jpg"));
+    }
+
+    #[test]
+    fn test_progressive_jpeg_output() {
+        let image = noisy_image(64, 48);
+        let has_marker = |data: &[u8], marker: u8| data.windows(2).any(|w| w == [0xFF, marker]);
+
+        let baseline = Compressor::new(85).compress_to_bytes(&image, image::ImageFormat::Jpeg).unwrap();
+        assert!(has_marker(&baseline, 0xC0));
+        assert!(!has_marker(&baseline, 0xC2));
+
+        let progressive = Compressor::new(85)
+            .with_progressive_jpeg(true)
+            .compress_to_bytes(&image, image::ImageFormat::Jpeg)
+            .unwrap();
+        assert!(has_marker(&progressive, 0xC2), "progressive frames use SOF2");
+        let decoded = image::load_from_memory(&progressive).unwrap();
+        assert_eq!(decoded.dimensions(), (64, 48));
+        assert!(ssim(&image, &decoded).unwrap() > 0.5);
+    }
+
+
+    #[test]
+    fn test_responsive_same_format_is_written_once() {
+        let input_dir = TempDir::new().unwrap();
+        let output_dir = TempDir::new().unwrap();
+        let input = input_dir.child("big.png");
+        noisy_image(200, 100).save(input.path()).unwrap();
+
+        // "same" resolves to PNG here, so it duplicates the explicit PNG entry
+        let spec = ResponsiveSpec {
+            widths: vec![50, 100],
+            formats: vec![pixie::OutputFormat::Png, pixie::OutputFormat::SameAsInput],
+            ..ResponsiveSpec::default()
+        };
+        let set = ResponsiveGenerator::new(ProcessConfig::default(), spec)
+            .generate(input.path(), output_dir.path())
+            .unwrap();
+
+        let files: Vec<&str> = set.variants.iter().map(|v| v.file.as_str()).collect();
+        assert_eq!(files, ["big-50w.png", "big-100w.png"]);
+        let html = set.picture_html("", "100vw", "");
+        assert_eq!(html.matches("big-50w.png").count(), 1);
+    }
+
+
+    #[test]
+    fn test_exif_thumbnail_is_never_transplanted() {
+        use exif::{Field, In, Tag, Value};
+
+        // No size tags and no rules, so nothing else would make the block get rewritten
+        let thumbnail = {
+            let mut buffer = std::io::Cursor::new(Vec::new());
+            DynamicImage::new_rgb8(2, 2).write_to(&mut buffer, image::ImageFormat::Jpeg).unwrap();
+            buffer.into_inner()
+        };
+        let copyright = Field { tag: Tag::Copyright, ifd_num: In::PRIMARY, value: Value::Ascii(vec![b"Jane Doe".to_vec()]) };
+        let compression = Field { tag: Tag::Compression, ifd_num: In::THUMBNAIL, value: Value::Short(vec![6]) };
+        let mut writer = exif::experimental::Writer::new();
+        writer.push_field(&copyright);
+        writer.push_field(&compression);
+        writer.set_jpeg(&thumbnail, In::THUMBNAIL);
+        let mut buffer = std::io::Cursor::new(Vec::new());
+        writer.write(&mut buffer, false).unwrap();
+        let input = encode_png_with_exif(&DynamicImage::new_rgb8(4, 4), buffer.into_inner());
+
+        for format in [pixie::OutputFormat::Jpeg, pixie::OutputFormat::Png, pixie::OutputFormat::WebP] {
+            let config = ProcessConfig { format: Some(format), ..Default::default() };
+            let (output, _) = ImageProcessor::new(config).process_bytes(&input).unwrap();
+            let exif = exif::Reader::new()
+                .read_from_container(&mut std::io::Cursor::new(&output))
+                .unwrap();
+            assert!(exif.get_field(Tag::Copyright, In::PRIMARY).is_some(), "{:?}", format);
+            assert!(!exif.fields().any(|field| field.ifd_num == In::THUMBNAIL), "{:?}", format);
+        }
+    }
+}